@@ -0,0 +1,106 @@
+//! `#[derive(DojoModel)]` for Bevy components mirroring a Dojo model.
+//!
+//! ```ignore
+//! #[derive(Component, Clone, PartialEq, DojoModel)]
+//! #[dojo(namespace = "grid_guru")]
+//! pub struct Tile {
+//!     pub game_id: u128,
+//!     pub x: u8,
+//!     pub y: u8,
+//!     pub owner: BevyFelt,
+//! }
+//! ```
+//!
+//! Every field is decoded from the model member of the same name. The model
+//! name defaults to the struct name and can be set with `name = "..."`.
+//! The generated code refers to the `frontend` crate as `::frontend`; set
+//! `crate = "..."` to another path, e.g. `crate = "crate"` inside it.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, LitStr, Path};
+
+#[proc_macro_derive(DojoModel, attributes(dojo))]
+pub fn derive_dojo_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let mut namespace = None;
+    let mut name = LitStr::new(&ident.to_string(), ident.span());
+    let mut krate: Path = parse_quote!(::frontend);
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("dojo"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("namespace") {
+                namespace = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else if meta.path.is_ident("name") {
+                name = meta.value()?.parse::<LitStr>()?;
+                Ok(())
+            } else if meta.path.is_ident("crate") {
+                krate = meta.value()?.parse::<LitStr>()?.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("expected `namespace`, `name` or `crate`"))
+            }
+        })?;
+    }
+    let namespace = namespace
+        .ok_or_else(|| syn::Error::new_spanned(ident, "missing #[dojo(namespace = \"...\")]"))?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "DojoModel needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "DojoModel can only be derived for structs",
+            ))
+        }
+    };
+    let members = fields.iter().map(|field| {
+        let field = field.ident.as_ref().unwrap();
+        let member = LitStr::new(&field.to_string(), field.span());
+        quote! { #field: #krate::plugins::dojo_models::decode::member(value, #member)? }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::core::convert::TryFrom<&#krate::plugins::dojo_models::DojoStruct>
+            for #ident #ty_generics #where_clause
+        {
+            type Error = #krate::plugins::dojo_models::decode::ModelDecodeError;
+
+            fn try_from(
+                value: &#krate::plugins::dojo_models::DojoStruct,
+            ) -> ::core::result::Result<Self, Self::Error> {
+                ::core::result::Result::Ok(#ident {
+                    #(#members,)*
+                })
+            }
+        }
+
+        impl #impl_generics #krate::plugins::dojo_models::DojoModel
+            for #ident #ty_generics #where_clause
+        {
+            const NAMESPACE: &'static str = #namespace;
+            const NAME: &'static str = #name;
+        }
+    })
+}
@@ -1,9 +1,15 @@
-mod plugins;
+pub mod plugins;
+pub mod rules;
 
 use bevy::prelude::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use plugins::{
-    dojo_models::DojoModelsPlugin, networking::NetworkingPlugin, render::GridGuruRenderPlugin,
+    ai::AiPlugin,
+    backend::{BackendKind, GameBackendPlugin},
+    dojo_models::DojoModelsPlugin,
+    local::LocalPlugin,
+    networking::{config::ProfileSettings, NetworkingPlugin},
+    render::GridGuruRenderPlugin,
     screens::GridGuruScreenPlugin,
 };
 
@@ -12,10 +18,14 @@ enum GameState {
     #[default]
     Loading,
     Playing,
+    /// A hot-seat game played against the local rules engine.
+    Local,
     Menu,
 }
 
-pub struct GamePlugin;
+pub struct GamePlugin {
+    pub settings: ProfileSettings,
+}
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(DefaultPlugins.set(WindowPlugin {
@@ -27,8 +37,17 @@ impl Plugin for GamePlugin {
         }));
         app.add_plugins(WorldInspectorPlugin::new());
         app.add_plugins(GridGuruScreenPlugin);
-        app.add_plugins(NetworkingPlugin);
-        app.add_plugins(DojoModelsPlugin);
+        app.insert_resource(self.settings.clone());
+        // Games on the memory backend never touch the chain or Torii.
+        if self.settings.backend == BackendKind::Starknet {
+            app.add_plugins(NetworkingPlugin);
+        }
+        app.add_plugins(GameBackendPlugin {
+            kind: self.settings.backend,
+        });
+        app.add_plugins(DojoModelsPlugin::default());
+        app.add_plugins(LocalPlugin);
+        app.add_plugins(AiPlugin);
         app.add_plugins(GridGuruRenderPlugin);
     }
 }
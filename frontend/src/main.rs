@@ -1,6 +1,12 @@
-use bevy::app::App;
-use frontend::GamePlugin;
+use bevy::app::{App, AppExit};
+use frontend::{plugins::networking::config::ProfileSettings, GamePlugin};
 
-fn main() {
-    App::new().add_plugins(GamePlugin).run();
+fn main() -> AppExit {
+    match ProfileSettings::load() {
+        Ok(settings) => App::new().add_plugins(GamePlugin { settings }).run(),
+        Err(e) => {
+            eprintln!("invalid configuration: {e}");
+            AppExit::error()
+        }
+    }
 }
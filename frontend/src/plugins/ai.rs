@@ -0,0 +1,268 @@
+//! A computer opponent for the active game. Offline it plays player two of
+//! the in-memory backend; on chain it signs with its own account, joining
+//! games that wait for a player and claiming tiles on its turns. Either way
+//! its moves go through the active backend as intents signed by
+//! [`Signer::Ai`].
+//!
+//! Set the starting difficulty with the `ai_difficulty` setting and cycle
+//! it with I.
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+
+use crate::plugins::backend::{memory::local_address, GameIntent, IntentSent, Signer};
+use crate::plugins::dojo_models::{Game, GameStatus, Player};
+use crate::plugins::networking::{
+    accounts::AccountConfig,
+    config::{NetworkConfig, ProfileSettings},
+    errors::GameError,
+    starknet_call::{ActiveGame, GridPos},
+    torii::BevyFelt,
+    transactions::{TxId, TxKind, TxStatus, TxStatusChanged},
+};
+use crate::rules::{
+    ai::{choose_move, Difficulty},
+    Side,
+};
+
+pub struct AiPlugin;
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        // Only sent with the networking plugin, read either way.
+        app.add_event::<TxStatusChanged>();
+        app.add_event::<IntentSent>();
+        app.init_resource::<AiProgress>();
+        app.add_systems(Startup, setup_ai);
+        app.add_systems(
+            Update,
+            pick_ai_account
+                .run_if(resource_exists::<AiOpponent>)
+                .run_if(resource_added::<NetworkConfig>),
+        );
+        app.add_systems(
+            Update,
+            (cycle_difficulty, play_ai_turn, retry_failed_turn)
+                .chain()
+                .run_if(resource_exists::<AiOpponent>),
+        );
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct AiOpponent {
+    /// `None` while the AI is switched off.
+    pub difficulty: Option<Difficulty>,
+    /// The account the command thread signs the AI's moves with on chain.
+    /// Without one it only plays offline games.
+    pub account: Option<AccountConfig>,
+}
+impl AiOpponent {
+    /// The address the AI plays `game` as. Offline games are played as
+    /// player two.
+    fn seat(&self, game: &Game) -> Option<BevyFelt> {
+        if game.player_one == local_address(Side::One) {
+            return Some(local_address(Side::Two));
+        }
+        Some(BevyFelt(self.account.as_ref()?.address))
+    }
+}
+
+/// The turn the AI last acted on, so it acts once per turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Turn {
+    game_id: u128,
+    move_count: u8,
+}
+
+/// How often the AI plays a turn again after its transaction failed.
+const MAX_TURN_RETRIES: u8 = 3;
+
+#[derive(Resource, Default)]
+struct AiProgress {
+    acted: Option<Turn>,
+    /// The transaction of that turn, once the backend sent it.
+    tx: Option<TxId>,
+    /// Set when that transaction failed and the turn is played again.
+    replay: bool,
+    /// How often the turn was played again so far.
+    retries: u8,
+    thinking: Option<(Turn, Task<Option<GridPos>>)>,
+}
+impl AiProgress {
+    fn act(&mut self, turn: Turn) {
+        if self.acted != Some(turn) {
+            self.retries = 0;
+        }
+        self.acted = Some(turn);
+        self.replay = false;
+    }
+}
+
+fn setup_ai(mut commands: Commands, settings: Res<ProfileSettings>) {
+    commands.insert_resource(AiOpponent {
+        difficulty: settings.ai_difficulty,
+        account: None,
+    });
+}
+
+/// Looks up the AI account once online play resolved the accounts.
+fn pick_ai_account(config: Res<NetworkConfig>, mut ai: ResMut<AiOpponent>) {
+    let Some(name) = config.ai_account.as_ref() else {
+        return;
+    };
+    ai.account = config.accounts.iter().find(|a| &a.name == name).cloned();
+    if ai.account.is_none() {
+        warn!("AI account '{name}' is not configured, the AI only plays offline");
+    }
+}
+
+fn cycle_difficulty(input: Res<ButtonInput<KeyCode>>, mut ai: ResMut<AiOpponent>) {
+    if !input.just_pressed(KeyCode::KeyI) {
+        return;
+    }
+
+    ai.difficulty = match ai.difficulty {
+        None => Some(Difficulty::Easy),
+        Some(Difficulty::Easy) => Some(Difficulty::Medium),
+        Some(Difficulty::Medium) => Some(Difficulty::Hard),
+        Some(Difficulty::Hard) => None,
+    };
+    match ai.difficulty {
+        Some(difficulty) => info!("AI opponent on {difficulty}"),
+        None => info!("AI opponent off"),
+    }
+}
+
+fn play_ai_turn(
+    ai: Res<AiOpponent>,
+    active_game: Res<ActiveGame>,
+    games: Query<&Game>,
+    players: Query<&Player>,
+    mut intents: EventWriter<GameIntent>,
+    mut progress: ResMut<AiProgress>,
+) {
+    let Some(difficulty) = ai.difficulty else {
+        progress.thinking = None;
+        return;
+    };
+    let Some(game) = games.iter().find(|g| g.game_id == active_game.game_id) else {
+        return;
+    };
+    let turn = Turn {
+        game_id: game.game_id,
+        move_count: game.move_count,
+    };
+    let Some(address) = ai.seat(game) else {
+        return;
+    };
+
+    let intent = if let Some((thinking, task)) = progress.thinking.as_mut() {
+        let Some(pos) = block_on(future::poll_once(task)) else {
+            return;
+        };
+        let thinking = *thinking;
+        progress.thinking = None;
+        if thinking != turn {
+            // The game moved on while the AI was thinking.
+            return;
+        }
+        match pos {
+            Some(pos) => GameIntent::ClaimTile {
+                game_id: game.game_id,
+                pos,
+                signer: Signer::Ai,
+            },
+            None => {
+                info!("AI has no tile left to claim in game {}", game.game_id);
+                progress.act(turn);
+                return;
+            }
+        }
+    } else if progress.acted == Some(turn) && !progress.replay {
+        return;
+    } else if game.status == GameStatus::Pending && game.player_one != address {
+        GameIntent::JoinGame {
+            game_id: game.game_id,
+            signer: Signer::Ai,
+        }
+    } else if game.status == GameStatus::InProgress && game.current_player == address {
+        let player = |address: BevyFelt| {
+            players
+                .iter()
+                .find(|p| p.game_id == game.game_id && p.address == address)
+        };
+        let (Some(one), Some(two)) = (player(game.player_one), player(game.player_two)) else {
+            return;
+        };
+        let Some(state) = game.state(one, Some(two)) else {
+            return;
+        };
+
+        let task = AsyncComputeTaskPool::get()
+            .spawn(async move { choose_move(&state, difficulty, &mut rand::thread_rng()) });
+        progress.thinking = Some((turn, task));
+        return;
+    } else {
+        return;
+    };
+
+    progress.act(turn);
+    progress.tx = None;
+    intents.send(intent);
+}
+
+/// Plays a turn again when its transaction did not go through, up to
+/// [`MAX_TURN_RETRIES`] times. Claims that broke the rules are planned
+/// again from the synced game; a join that broke them is given up on, as
+/// sending it again would fail the same way.
+fn retry_failed_turn(
+    mut sent: EventReader<IntentSent>,
+    mut updates: EventReader<TxStatusChanged>,
+    mut progress: ResMut<AiProgress>,
+) {
+    for sent in sent.read() {
+        if sent.intent.signer() == Signer::Ai {
+            progress.tx = Some(sent.tx);
+        }
+    }
+    for update in updates.read() {
+        if progress.tx != Some(update.id) || !update.status.is_final() {
+            continue;
+        }
+        progress.tx = None;
+
+        let error = match &update.status {
+            // The same transaction is already on its way.
+            TxStatus::Succeeded(_) | TxStatus::Duplicate => continue,
+            TxStatus::Reverted { reason, .. } => Some(GameError::from_revert_reason(reason)),
+            TxStatus::SimulationFailed(reason) | TxStatus::Failed(reason) => {
+                GameError::find_in(reason)
+            }
+            _ => None,
+        };
+        if error.is_some() && update.kind != TxKind::ClaimTile {
+            warn!(
+                "AI transaction {:?} ({:?}) broke the rules, giving up: {:?}",
+                update.id, update.kind, update.status
+            );
+            continue;
+        }
+        if progress.retries >= MAX_TURN_RETRIES {
+            warn!(
+                "AI transaction {:?} failed {} times, giving up on this turn",
+                update.id,
+                progress.retries + 1
+            );
+            continue;
+        }
+
+        match error {
+            Some(error) => warn!("AI claim {:?} failed ({error}), planning again", update.id),
+            None => warn!("AI transaction {:?} failed, retrying", update.id),
+        }
+        progress.retries += 1;
+        progress.replay = true;
+    }
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+use crate::plugins::networking::{
+    starknet_call::{ActiveGame, GridPos, StarknetChannel, StarknetCommands},
+    transactions::TxId,
+};
+
+use super::{BackendError, GameBackend, Signer};
+
+/// Sends intents as transactions through the command thread. Torii syncs
+/// the resulting state for the active game.
+pub struct StarknetBackend;
+impl StarknetBackend {
+    fn send(
+        world: &mut World,
+        signer: Signer,
+        command: StarknetCommands,
+    ) -> Result<Option<TxId>, BackendError> {
+        let channel = world
+            .get_resource::<StarknetChannel>()
+            .ok_or(BackendError::NotConnected)?;
+        channel
+            .send_as(signer, command)
+            .map(Some)
+            .ok_or(BackendError::QueueFull)
+    }
+}
+impl GameBackend for StarknetBackend {
+    fn create_game(&mut self, world: &mut World) -> Result<Option<TxId>, BackendError> {
+        StarknetBackend::send(world, Signer::Player, StarknetCommands::SendCreateGameTx)
+    }
+
+    fn join_game(
+        &mut self,
+        world: &mut World,
+        game_id: u128,
+        signer: Signer,
+    ) -> Result<Option<TxId>, BackendError> {
+        StarknetBackend::send(world, signer, StarknetCommands::SendJoinGameTx { game_id })
+    }
+
+    fn claim_tile(
+        &mut self,
+        world: &mut World,
+        game_id: u128,
+        pos: GridPos,
+        signer: Signer,
+    ) -> Result<Option<TxId>, BackendError> {
+        StarknetBackend::send(
+            world,
+            signer,
+            StarknetCommands::SendClaimTileTx { game_id, pos },
+        )
+    }
+
+    /// Torii follows the active game, see `follow_active_game`.
+    fn subscribe(&mut self, world: &mut World, game_id: u128) {
+        world
+            .resource_mut::<ActiveGame>()
+            .set_if_neq(ActiveGame { game_id });
+    }
+}
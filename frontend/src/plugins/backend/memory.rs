@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use starknet::core::types::Felt;
+
+use crate::plugins::dojo_models::{DojoModel, Game, Player, Tile};
+use crate::plugins::networking::starknet_call::{ActiveGame, GridPos};
+use crate::plugins::networking::{torii::BevyFelt, transactions::TxId};
+use crate::rules::{self, GameStatus, Side};
+
+use super::{BackendError, GameBackend, Signer};
+
+/// Runs games on the rules engine, for players sharing this machine. Each
+/// game is mirrored into the components Torii would sync for it, so
+/// everything rendering them works the same offline.
+///
+/// Creating a game seats player one, joining it seats player two, and
+/// claims are made by whoever's turn it is, whichever signer sends them.
+/// Marks the entities a [`MemoryBackend`] spawned, so they can be despawned
+/// with the games they belong to.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct MemoryModel;
+
+#[derive(Default)]
+pub struct MemoryBackend {
+    games: HashMap<u128, MemoryGame>,
+}
+
+struct MemoryGame {
+    state: rules::GameState,
+    game: Entity,
+    players: [Option<Entity>; 2],
+}
+
+impl MemoryBackend {
+    /// Id of the first game created. Ids count down from here, as game ids
+    /// on chain count up from one and must not collide with synced games.
+    pub const FIRST_GAME_ID: u128 = u128::MAX;
+
+    fn game(&mut self, game_id: u128) -> Result<&mut MemoryGame, BackendError> {
+        self.games
+            .get_mut(&game_id)
+            .ok_or(BackendError::UnknownGame(game_id))
+    }
+}
+
+impl GameBackend for MemoryBackend {
+    fn create_game(&mut self, world: &mut World) -> Result<Option<TxId>, BackendError> {
+        let game_id = MemoryBackend::FIRST_GAME_ID - self.games.len() as u128;
+        let state = rules::GameState::pending();
+        let game = world.spawn((game_model(game_id, &state), MemoryModel)).id();
+        let player = world
+            .spawn((player_model(game_id, &state, Side::One), MemoryModel))
+            .id();
+        world.spawn((
+            tile_model(game_id, Side::One.start(), Side::One),
+            MemoryModel,
+        ));
+
+        self.games.insert(
+            game_id,
+            MemoryGame {
+                state,
+                game,
+                players: [Some(player), None],
+            },
+        );
+        info!("created local game {game_id}");
+        self.subscribe(world, game_id);
+        Ok(None)
+    }
+
+    fn join_game(
+        &mut self,
+        world: &mut World,
+        game_id: u128,
+        _signer: Signer,
+    ) -> Result<Option<TxId>, BackendError> {
+        let game = self.game(game_id)?;
+        game.state = game.state.join()?;
+
+        let player = player_model(game_id, &game.state, Side::Two);
+        game.players[Side::Two.seat()] = Some(world.spawn((player, MemoryModel)).id());
+        world.spawn((
+            tile_model(game_id, Side::Two.start(), Side::Two),
+            MemoryModel,
+        ));
+        game_model(game_id, &game.state).upsert(&mut world.commands().entity(game.game));
+        Ok(None)
+    }
+
+    fn claim_tile(
+        &mut self,
+        world: &mut World,
+        game_id: u128,
+        pos: GridPos,
+        _signer: Signer,
+    ) -> Result<Option<TxId>, BackendError> {
+        let game = self.game(game_id)?;
+        let side = game.state.current;
+        game.state = end_if_stuck(game.state.apply_move(pos)?);
+
+        world.spawn((tile_model(game_id, pos, side), MemoryModel));
+        let mut commands = world.commands();
+        if let Some(player) = game.players[side.seat()] {
+            player_model(game_id, &game.state, side).upsert(&mut commands.entity(player));
+        }
+        game_model(game_id, &game.state).upsert(&mut commands.entity(game.game));
+        if let Some(winner) = game.state.winner {
+            info!("player {winner:?} won local game {game_id}");
+        }
+        Ok(None)
+    }
+
+    fn subscribe(&mut self, world: &mut World, game_id: u128) {
+        world
+            .resource_mut::<ActiveGame>()
+            .set_if_neq(ActiveGame { game_id });
+    }
+}
+
+/// Ends a game whose side to move has no tile left to claim, which the
+/// contracts leave running. The other side wins, as when a move leaves the
+/// mover without moves.
+fn end_if_stuck(mut state: rules::GameState) -> rules::GameState {
+    if state.status == GameStatus::InProgress && state.legal_moves().is_empty() {
+        state.status = GameStatus::Completed;
+        state.winner = Some(state.current.opponent());
+    }
+    state
+}
+
+/// Stand-in address of each side, as the models need one.
+pub fn local_address(side: Side) -> BevyFelt {
+    match side {
+        Side::One => BevyFelt(Felt::ONE),
+        Side::Two => BevyFelt(Felt::TWO),
+    }
+}
+
+fn game_model(game_id: u128, state: &rules::GameState) -> Game {
+    let player_two = if state.grid(Side::Two).is_empty() {
+        BevyFelt(Felt::ZERO)
+    } else {
+        local_address(Side::Two)
+    };
+    Game {
+        game_id,
+        player_one: local_address(Side::One),
+        player_two,
+        current_player: local_address(state.current),
+        winner: state.winner.map_or(BevyFelt(Felt::ZERO), local_address),
+        move_count: state.move_count,
+        status: state.status,
+    }
+}
+
+fn player_model(game_id: u128, state: &rules::GameState, side: Side) -> Player {
+    let pos = state.position(side);
+    Player {
+        game_id,
+        address: local_address(side),
+        score: 0,
+        grid: BevyFelt(state.grid(side).to_felt()),
+        x: pos.x,
+        y: pos.y,
+    }
+}
+
+fn tile_model(game_id: u128, pos: GridPos, side: Side) -> Tile {
+    Tile {
+        game_id,
+        x: pos.x,
+        y: pos.y,
+        owner: local_address(side),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rules::Bitboard;
+
+    use super::*;
+
+    fn board(tiles: &[(u8, u8)]) -> Bitboard {
+        tiles.iter().fold(Bitboard::EMPTY, |board, (x, y)| {
+            board.with(GridPos::new(*x, *y))
+        })
+    }
+
+    #[test]
+    fn ends_the_game_when_the_side_to_move_is_walled_in() {
+        let mut world = World::new();
+        let game_id = MemoryBackend::FIRST_GAME_ID;
+        let game = world.spawn(MemoryModel).id();
+        // Player two is about to close off player one's corner.
+        let state = rules::GameState::from_grids(
+            [board(&[(0, 0)]), board(&[(1, 0), (1, 1)])],
+            [GridPos::new(0, 0), GridPos::new(1, 1)],
+            Side::Two,
+        );
+        let mut backend = MemoryBackend::default();
+        backend.games.insert(
+            game_id,
+            MemoryGame {
+                state,
+                game,
+                players: [None, None],
+            },
+        );
+
+        backend
+            .claim_tile(&mut world, game_id, GridPos::new(0, 1), Signer::Player)
+            .unwrap();
+        world.flush();
+
+        let synced = world.get::<Game>(game).unwrap();
+        assert_eq!(synced.status, GameStatus::Completed);
+        assert_eq!(synced.winner, local_address(Side::Two));
+        assert_eq!(
+            backend.claim_tile(&mut world, game_id, GridPos::new(2, 0), Signer::Player),
+            Err(BackendError::Rules(rules::GameError::GameNotInProgress))
+        );
+    }
+
+    #[test]
+    fn keeps_playing_while_the_side_to_move_can_claim() {
+        let state = rules::GameState::new();
+
+        assert_eq!(end_if_stuck(state), state);
+    }
+}
@@ -0,0 +1,298 @@
+//! Where games are executed. Gameplay systems send [`GameIntent`]s and the
+//! [`ActiveBackend`] carries them out, on chain or in process. Either way
+//! the resulting state shows up as `Game`, `Player` and `Tile` components.
+
+pub mod chain;
+pub mod memory;
+
+use std::{fmt, str::FromStr};
+
+use bevy::prelude::*;
+
+pub use crate::plugins::networking::starknet_call::Signer;
+use crate::plugins::networking::{
+    errors::GameError,
+    starknet_call::{ActiveGame, ClaimTilePosition, GridPos},
+    transactions::TxId,
+};
+use chain::StarknetBackend;
+use memory::MemoryBackend;
+
+pub struct GameBackendPlugin {
+    pub kind: BackendKind,
+}
+impl Plugin for GameBackendPlugin {
+    fn build(&self, app: &mut App) {
+        info!("using the {:?} game backend", self.kind);
+        app.insert_resource(ActiveBackend(self.kind.backend()));
+        app.insert_resource(ActiveGame { game_id: 1 });
+        app.insert_resource(ClaimTilePosition {
+            pos: GridPos { x: 10, y: 10 },
+            current_selection: false,
+        });
+        app.add_event::<GameIntent>();
+        app.add_event::<IntentSent>();
+        app.add_systems(Update, select_claim_tile);
+        app.add_systems(Update, (handle_player_inputs, execute_intents).chain());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    /// Transactions to the `actions` contract, state synced from Torii.
+    Starknet,
+    /// Games played against the local rules engine.
+    Memory,
+}
+impl BackendKind {
+    pub fn backend(self) -> Box<dyn GameBackend> {
+        match self {
+            BackendKind::Starknet => Box::new(StarknetBackend),
+            BackendKind::Memory => Box::new(MemoryBackend::default()),
+        }
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = String;
+
+    /// Parses the `backend` setting.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "starknet" => Ok(BackendKind::Starknet),
+            "memory" => Ok(BackendKind::Memory),
+            other => Err(format!(
+                "unknown backend '{other}', expected 'starknet' or 'memory'"
+            )),
+        }
+    }
+}
+
+/// Something a player wants done to a game. Joins and claims say who makes
+/// them, so the AI plays with its own account.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameIntent {
+    CreateGame,
+    JoinGame {
+        game_id: u128,
+        signer: Signer,
+    },
+    ClaimTile {
+        game_id: u128,
+        pos: GridPos,
+        signer: Signer,
+    },
+    Subscribe {
+        game_id: u128,
+    },
+}
+
+impl GameIntent {
+    /// Who makes the intent. Creating games and following them is always
+    /// up to the player.
+    pub fn signer(&self) -> Signer {
+        match self {
+            GameIntent::JoinGame { signer, .. } | GameIntent::ClaimTile { signer, .. } => *signer,
+            GameIntent::CreateGame | GameIntent::Subscribe { .. } => Signer::Player,
+        }
+    }
+}
+
+/// An intent the backend sent as transaction `tx`.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntentSent {
+    pub intent: GameIntent,
+    pub tx: TxId,
+}
+
+/// Executes intents against some source of truth for games. Backends get
+/// the whole world so they can reach the resources they talk through and
+/// write the components of the games they run. Backends that send
+/// transactions return their id.
+pub trait GameBackend: Send + Sync + 'static {
+    fn create_game(&mut self, world: &mut World) -> Result<Option<TxId>, BackendError>;
+    fn join_game(
+        &mut self,
+        world: &mut World,
+        game_id: u128,
+        signer: Signer,
+    ) -> Result<Option<TxId>, BackendError>;
+    fn claim_tile(
+        &mut self,
+        world: &mut World,
+        game_id: u128,
+        pos: GridPos,
+        signer: Signer,
+    ) -> Result<Option<TxId>, BackendError>;
+    /// Starts showing the state of `game_id`.
+    fn subscribe(&mut self, world: &mut World, game_id: u128);
+}
+
+#[derive(Resource)]
+pub struct ActiveBackend(pub Box<dyn GameBackend>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendError {
+    /// The command thread has not started yet.
+    NotConnected,
+    /// Too many commands are waiting for the command thread.
+    QueueFull,
+    UnknownGame(u128),
+    /// The move breaks the game rules.
+    Rules(GameError),
+}
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::NotConnected => write!(f, "not connected to Starknet yet"),
+            BackendError::QueueFull => write!(f, "too many pending commands"),
+            BackendError::UnknownGame(game_id) => write!(f, "game {game_id} does not exist"),
+            BackendError::Rules(e) => write!(f, "{e}"),
+        }
+    }
+}
+impl std::error::Error for BackendError {}
+impl From<GameError> for BackendError {
+    fn from(value: GameError) -> Self {
+        BackendError::Rules(value)
+    }
+}
+
+/// Picks the claim tile with the digit keys, X and Y choosing the axis.
+fn select_claim_tile(
+    input: Res<ButtonInput<KeyCode>>,
+    mut claim_resource: ResMut<ClaimTilePosition>,
+) {
+    let keys = input.get_just_pressed();
+    let mut modify_inputs = false;
+    let mut value = 10;
+
+    for key in keys {
+        match key {
+            KeyCode::Digit0 => {
+                modify_inputs = true;
+                value = 0;
+            }
+            KeyCode::Digit1 => {
+                modify_inputs = true;
+                value = 1;
+            }
+            KeyCode::Digit2 => {
+                modify_inputs = true;
+                value = 2;
+            }
+            KeyCode::Digit3 => {
+                modify_inputs = true;
+                value = 3;
+            }
+            KeyCode::Digit4 => {
+                modify_inputs = true;
+                value = 4;
+            }
+            KeyCode::Digit5 => {
+                modify_inputs = true;
+                value = 5;
+            }
+            KeyCode::Digit6 => {
+                modify_inputs = true;
+                value = 6;
+            }
+            KeyCode::Digit7 => {
+                modify_inputs = true;
+                value = 7;
+            }
+            KeyCode::KeyX => {
+                claim_resource.current_selection = false;
+            }
+            KeyCode::KeyY => {
+                claim_resource.current_selection = true;
+            }
+            _ => {}
+        }
+
+        if modify_inputs {
+            if claim_resource.current_selection {
+                claim_resource.pos.y = value;
+                info!("changed y selection to {value}");
+            } else {
+                claim_resource.pos.x = value;
+                info!("changed x selection to {value}");
+            }
+        }
+    }
+}
+
+fn handle_player_inputs(
+    input: Res<ButtonInput<KeyCode>>,
+    active_game: Res<ActiveGame>,
+    claim_resource: Res<ClaimTilePosition>,
+    mut intents: EventWriter<GameIntent>,
+) {
+    for key in input.get_just_pressed() {
+        match key {
+            KeyCode::BracketLeft if active_game.game_id > 1 => {
+                intents.send(GameIntent::Subscribe {
+                    game_id: active_game.game_id - 1,
+                });
+            }
+            KeyCode::BracketRight if active_game.game_id < u128::MAX => {
+                intents.send(GameIntent::Subscribe {
+                    game_id: active_game.game_id + 1,
+                });
+            }
+            KeyCode::KeyN => {
+                intents.send(GameIntent::CreateGame);
+            }
+            KeyCode::KeyJ => {
+                intents.send(GameIntent::JoinGame {
+                    game_id: active_game.game_id,
+                    signer: Signer::Player,
+                });
+            }
+            KeyCode::Space => {
+                intents.send(GameIntent::ClaimTile {
+                    game_id: active_game.game_id,
+                    pos: claim_resource.pos,
+                    signer: Signer::Player,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn execute_intents(world: &mut World) {
+    let intents: Vec<GameIntent> = world.resource_mut::<Events<GameIntent>>().drain().collect();
+    if intents.is_empty() {
+        return;
+    }
+
+    world.resource_scope(|world, mut backend: Mut<ActiveBackend>| {
+        for intent in intents {
+            let result = match intent {
+                GameIntent::CreateGame => backend.0.create_game(world),
+                GameIntent::JoinGame { game_id, signer } => {
+                    backend.0.join_game(world, game_id, signer)
+                }
+                GameIntent::ClaimTile {
+                    game_id,
+                    pos,
+                    signer,
+                } => backend.0.claim_tile(world, game_id, pos, signer),
+                GameIntent::Subscribe { game_id } => {
+                    backend.0.subscribe(world, game_id);
+                    info!("switched to game {game_id}");
+                    Ok(None)
+                }
+            };
+            match result {
+                Ok(Some(tx)) => {
+                    world.send_event(IntentSent { intent, tx });
+                }
+                Ok(None) => {}
+                Err(e) => warn!("cannot execute {intent:?}: {e}"),
+            }
+        }
+    });
+    world.flush();
+}
@@ -0,0 +1,407 @@
+use std::fmt;
+
+use dojo_types::{
+    primitive::Primitive,
+    schema::{Struct as DojoStruct, Ty},
+};
+
+use crate::plugins::networking::torii::BevyFelt;
+
+use super::GameStatus;
+
+/// Why a Dojo model could not be turned into a component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelDecodeError {
+    MissingMember {
+        model: String,
+        member: &'static str,
+    },
+    UnexpectedType {
+        model: String,
+        member: &'static str,
+        expected: &'static str,
+        found: String,
+    },
+    /// The member is a variant this client does not know, e.g. one added to
+    /// the Cairo enum after this build.
+    UnknownVariant {
+        model: String,
+        member: &'static str,
+        variant: String,
+    },
+}
+impl fmt::Display for ModelDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelDecodeError::MissingMember { model, member } => {
+                write!(f, "model '{model}' has no member '{member}'")
+            }
+            ModelDecodeError::UnexpectedType {
+                model,
+                member,
+                expected,
+                found,
+            } => write!(
+                f,
+                "member '{member}' of model '{model}' is not a {expected}: {found}"
+            ),
+            ModelDecodeError::UnknownVariant {
+                model,
+                member,
+                variant,
+            } => write!(
+                f,
+                "member '{member}' of model '{model}' has unknown variant '{variant}'"
+            ),
+        }
+    }
+}
+impl std::error::Error for ModelDecodeError {}
+
+/// Why a member value could not be read as a Rust type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromTyError {
+    UnexpectedType,
+    UnknownVariant(String),
+}
+
+/// A Rust type a model member can be read as.
+pub trait FromDojoTy: Sized {
+    /// Name of the expected Cairo type, for errors.
+    const EXPECTED: &'static str;
+
+    fn from_dojo_ty(ty: &Ty) -> Result<Self, FromTyError>;
+}
+
+impl FromDojoTy for u8 {
+    const EXPECTED: &'static str = "u8";
+
+    fn from_dojo_ty(ty: &Ty) -> Result<Self, FromTyError> {
+        ty.as_primitive()
+            .and_then(Primitive::as_u8)
+            .ok_or(FromTyError::UnexpectedType)
+    }
+}
+
+impl FromDojoTy for u128 {
+    const EXPECTED: &'static str = "u128";
+
+    fn from_dojo_ty(ty: &Ty) -> Result<Self, FromTyError> {
+        ty.as_primitive()
+            .and_then(Primitive::as_u128)
+            .ok_or(FromTyError::UnexpectedType)
+    }
+}
+
+impl FromDojoTy for BevyFelt {
+    const EXPECTED: &'static str = "felt252 or ContractAddress";
+
+    fn from_dojo_ty(ty: &Ty) -> Result<Self, FromTyError> {
+        match ty.as_primitive() {
+            Some(Primitive::Felt252(Some(felt)) | Primitive::ContractAddress(Some(felt))) => {
+                Ok((*felt).into())
+            }
+            _ => Err(FromTyError::UnexpectedType),
+        }
+    }
+}
+
+impl FromDojoTy for GameStatus {
+    const EXPECTED: &'static str = "GameStatus";
+
+    /// Decodes by variant name, so reordering the Cairo enum cannot shift
+    /// statuses.
+    fn from_dojo_ty(ty: &Ty) -> Result<Self, FromTyError> {
+        let option = ty
+            .as_enum()
+            .and_then(|status| status.options.get(status.option? as usize))
+            .ok_or(FromTyError::UnexpectedType)?;
+        match option.name.as_str() {
+            "Pending" => Ok(GameStatus::Pending),
+            "InProgress" => Ok(GameStatus::InProgress),
+            "Completed" => Ok(GameStatus::Completed),
+            "Draw" => Ok(GameStatus::Draw),
+            "Abandoned" => Ok(GameStatus::Abandoned),
+            "TimedOut" => Ok(GameStatus::TimedOut),
+            other => Err(FromTyError::UnknownVariant(other.to_string())),
+        }
+    }
+}
+
+/// Reads the member called `name` of `model`.
+pub fn member<T: FromDojoTy>(
+    model: &DojoStruct,
+    name: &'static str,
+) -> Result<T, ModelDecodeError> {
+    let child = model
+        .children
+        .iter()
+        .find(|child| child.name == name)
+        .ok_or_else(|| ModelDecodeError::MissingMember {
+            model: model.name.clone(),
+            member: name,
+        })?;
+
+    T::from_dojo_ty(&child.ty).map_err(|e| match e {
+        FromTyError::UnexpectedType => ModelDecodeError::UnexpectedType {
+            model: model.name.clone(),
+            member: name,
+            expected: T::EXPECTED,
+            found: format!("{:?}", child.ty),
+        },
+        FromTyError::UnknownVariant(variant) => ModelDecodeError::UnknownVariant {
+            model: model.name.clone(),
+            member: name,
+            variant,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use dojo_types::schema::{Enum, EnumOption, Member};
+    use starknet::core::types::Felt;
+
+    use super::*;
+    use crate::plugins::dojo_models::{Game, Player, Tile};
+
+    fn field(name: &str, ty: Ty, key: bool) -> Member {
+        Member {
+            name: name.to_string(),
+            ty,
+            key,
+        }
+    }
+
+    fn model(name: &str, children: Vec<Member>) -> DojoStruct {
+        DojoStruct {
+            name: name.to_string(),
+            children,
+        }
+    }
+
+    fn address(value: u64) -> Ty {
+        Ty::Primitive(Primitive::ContractAddress(Some(Felt::from(value))))
+    }
+
+    const STATUSES: [&str; 6] = [
+        "Pending",
+        "InProgress",
+        "Completed",
+        "Draw",
+        "Abandoned",
+        "TimedOut",
+    ];
+
+    fn status(option: u8) -> Ty {
+        status_of(&STATUSES, option)
+    }
+
+    fn status_of(options: &[&str], option: u8) -> Ty {
+        Ty::Enum(Enum {
+            name: "GameStatus".to_string(),
+            option: Some(option),
+            options: options
+                .iter()
+                .map(|name| EnumOption {
+                    name: name.to_string(),
+                    ty: Ty::Tuple(vec![]),
+                })
+                .collect(),
+        })
+    }
+
+    fn tile_model() -> DojoStruct {
+        model(
+            "grid_guru-Tile",
+            vec![
+                field("game_id", Ty::Primitive(Primitive::U128(Some(1))), true),
+                field("x", Ty::Primitive(Primitive::U8(Some(3))), true),
+                field("y", Ty::Primitive(Primitive::U8(Some(4))), true),
+                field("owner", address(0xabc), false),
+            ],
+        )
+    }
+
+    #[test]
+    fn decodes_tile() {
+        let tile = Tile::try_from(&tile_model()).unwrap();
+
+        assert_eq!(tile.game_id, 1);
+        assert_eq!((tile.x, tile.y), (3, 4));
+        assert_eq!(tile.owner, BevyFelt::from(Felt::from(0xabc_u64)));
+    }
+
+    /// A model only this test knows, to check what the derive generates
+    /// for the `crate` and `name` attributes.
+    #[derive(
+        bevy::prelude::Component, Debug, Clone, PartialEq, crate::plugins::dojo_models::DojoModel,
+    )]
+    #[dojo(crate = "crate", namespace = "test", name = "Marker")]
+    struct RenamedTile {
+        x: u8,
+        owner: BevyFelt,
+    }
+
+    #[test]
+    fn derives_a_renamed_model() {
+        use crate::plugins::dojo_models::DojoModel;
+
+        let marker = RenamedTile::try_from(&tile_model()).unwrap();
+
+        assert_eq!(RenamedTile::tag(), "test-Marker");
+        assert_eq!(
+            marker,
+            RenamedTile {
+                x: 3,
+                owner: BevyFelt::from(Felt::from(0xabc_u64)),
+            }
+        );
+        assert_eq!(
+            RenamedTile::try_from(&model("test-Marker", vec![])),
+            Err(ModelDecodeError::MissingMember {
+                model: "test-Marker".to_string(),
+                member: "x",
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_members_in_any_order() {
+        let mut shuffled = tile_model();
+        shuffled.children.reverse();
+
+        let tile = Tile::try_from(&shuffled).unwrap();
+
+        assert_eq!((tile.game_id, tile.x, tile.y), (1, 3, 4));
+    }
+
+    #[test]
+    fn decodes_player_grid_as_felt() {
+        let player = model(
+            "grid_guru-Player",
+            vec![
+                field("game_id", Ty::Primitive(Primitive::U128(Some(1))), true),
+                field("address", address(0x1), true),
+                field("score", Ty::Primitive(Primitive::U8(Some(2))), false),
+                field(
+                    "grid",
+                    Ty::Primitive(Primitive::Felt252(Some(Felt::from(0x8000_u64)))),
+                    false,
+                ),
+                field("x", Ty::Primitive(Primitive::U8(Some(0))), false),
+                field("y", Ty::Primitive(Primitive::U8(Some(1))), false),
+            ],
+        );
+
+        let player = Player::try_from(&player).unwrap();
+
+        assert_eq!(player.score, 2);
+        assert_eq!(player.grid, BevyFelt::from(Felt::from(0x8000_u64)));
+        assert_eq!((player.x, player.y), (0, 1));
+    }
+
+    #[test]
+    fn decodes_game_status() {
+        let game = model(
+            "grid_guru-Game",
+            vec![
+                field("game_id", Ty::Primitive(Primitive::U128(Some(7))), true),
+                field("player_one", address(0x1), false),
+                field("player_two", address(0x2), false),
+                field("current_player", address(0x2), false),
+                field("winner", address(0x0), false),
+                field("move_count", Ty::Primitive(Primitive::U8(Some(5))), false),
+                field("status", status(1), false),
+            ],
+        );
+
+        let game = Game::try_from(&game).unwrap();
+
+        assert_eq!(game.game_id, 7);
+        assert_eq!(game.move_count, 5);
+        assert_eq!(game.status, GameStatus::InProgress);
+    }
+
+    #[test]
+    fn reports_missing_member() {
+        let mut tile = tile_model();
+        tile.children.retain(|child| child.name != "owner");
+
+        let error = Tile::try_from(&tile).unwrap_err();
+
+        assert_eq!(
+            error,
+            ModelDecodeError::MissingMember {
+                model: "grid_guru-Tile".to_string(),
+                member: "owner",
+            }
+        );
+    }
+
+    #[test]
+    fn reports_unexpected_type() {
+        let mut tile = tile_model();
+        tile.children[1].ty = Ty::Primitive(Primitive::U32(Some(3)));
+
+        let error = Tile::try_from(&tile).unwrap_err();
+
+        assert!(matches!(
+            error,
+            ModelDecodeError::UnexpectedType {
+                member: "x",
+                expected: "u8",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_enum_option() {
+        let error = member::<GameStatus>(
+            &model("grid_guru-Game", vec![field("status", status(9), false)]),
+            "status",
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            error,
+            ModelDecodeError::UnexpectedType {
+                member: "status",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn decodes_game_status_by_name() {
+        let reordered = status_of(&["Completed", "Pending", "InProgress"], 0);
+
+        assert_eq!(
+            GameStatus::from_dojo_ty(&reordered),
+            Ok(GameStatus::Completed)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_game_status() {
+        let mut statuses = STATUSES.to_vec();
+        statuses.push("Paused");
+        let status = status_of(&statuses, 6);
+
+        let error = member::<GameStatus>(
+            &model("grid_guru-Game", vec![field("status", status, false)]),
+            "status",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            ModelDecodeError::UnknownVariant {
+                model: "grid_guru-Game".to_string(),
+                member: "status",
+                variant: "Paused".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::plugins::networking::{starknet_call::GridPos, torii::BevyFelt};
+
+use super::{Game, GameStatus, Tile};
+
+/// Turns changes of the synced models into game events.
+pub struct DojoEventsPlugin;
+impl Plugin for DojoEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TileClaimed>();
+        app.add_event::<TurnChanged>();
+        app.add_event::<PlayerJoined>();
+        app.add_event::<GameStatusChanged>();
+        app.add_event::<GameWon>();
+        app.add_systems(Update, (emit_tile_events, emit_game_events));
+    }
+}
+
+/// A tile was claimed, or first seen during a sync.
+#[derive(Event, Debug, Clone)]
+pub struct TileClaimed {
+    pub game_id: u128,
+    pub pos: GridPos,
+    pub owner: BevyFelt,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct TurnChanged {
+    pub game_id: u128,
+    pub current_player: BevyFelt,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct PlayerJoined {
+    pub game_id: u128,
+    pub player: BevyFelt,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct GameStatusChanged {
+    pub game_id: u128,
+    pub from: GameStatus,
+    pub to: GameStatus,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct GameWon {
+    pub game_id: u128,
+    pub winner: BevyFelt,
+}
+
+fn emit_tile_events(tiles: Query<&Tile, Changed<Tile>>, mut claimed: EventWriter<TileClaimed>) {
+    for tile in tiles.iter() {
+        claimed.send(TileClaimed {
+            game_id: tile.game_id,
+            pos: GridPos {
+                x: tile.x,
+                y: tile.y,
+            },
+            owner: tile.owner,
+        });
+    }
+}
+
+/// Diffs every changed game against the last version seen on the same
+/// entity. The first version of a game only sets the baseline, and a game
+/// that is despawned or loses its model is forgotten.
+fn emit_game_events(
+    games: Query<(Entity, &Game), Changed<Game>>,
+    mut removed: RemovedComponents<Game>,
+    mut seen: Local<HashMap<Entity, Game>>,
+    mut turns: EventWriter<TurnChanged>,
+    mut joins: EventWriter<PlayerJoined>,
+    mut statuses: EventWriter<GameStatusChanged>,
+    mut wins: EventWriter<GameWon>,
+) {
+    for entity in removed.read() {
+        seen.remove(&entity);
+    }
+    for (entity, game) in games.iter() {
+        let Some(previous) = seen.insert(entity, game.clone()) else {
+            continue;
+        };
+        let game_id = game.game_id;
+
+        if previous.player_two.is_zero() && !game.player_two.is_zero() {
+            joins.send(PlayerJoined {
+                game_id,
+                player: game.player_two,
+            });
+        }
+        if previous.current_player != game.current_player {
+            turns.send(TurnChanged {
+                game_id,
+                current_player: game.current_player,
+            });
+        }
+        if previous.status != game.status {
+            statuses.send(GameStatusChanged {
+                game_id,
+                from: previous.status,
+                to: game.status,
+            });
+        }
+        if previous.winner.is_zero() && !game.winner.is_zero() {
+            wins.send(GameWon {
+                game_id,
+                winner: game.winner,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet::core::types::Felt;
+
+    use super::super::DojoModel;
+    use super::*;
+
+    fn felt(value: u64) -> BevyFelt {
+        BevyFelt(Felt::from(value))
+    }
+
+    fn pending_game() -> Game {
+        Game {
+            game_id: 7,
+            player_one: felt(1),
+            player_two: felt(0),
+            current_player: felt(1),
+            winner: felt(0),
+            move_count: 0,
+            status: GameStatus::Pending,
+        }
+    }
+
+    /// Upserts `game` onto `entity` the way a Torii sync does.
+    fn sync(app: &mut App, entity: Entity, game: Game) {
+        game.upsert(&mut app.world_mut().commands().entity(entity));
+        app.world_mut().flush();
+    }
+
+    fn sent<E: Event + Clone>(app: &App) -> Vec<E> {
+        app.world()
+            .resource::<Events<E>>()
+            .iter_current_update_events()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn emits_the_changes_between_upserts() {
+        let mut app = App::new();
+        app.add_plugins(DojoEventsPlugin);
+        let entity = app.world_mut().spawn_empty().id();
+        sync(&mut app, entity, pending_game());
+        app.update();
+        assert!(sent::<TurnChanged>(&app).is_empty());
+
+        let joined = Game {
+            player_two: felt(2),
+            current_player: felt(2),
+            status: GameStatus::InProgress,
+            ..pending_game()
+        };
+        sync(&mut app, entity, joined.clone());
+        app.update();
+
+        let joins = sent::<PlayerJoined>(&app);
+        let turns = sent::<TurnChanged>(&app);
+        let statuses = sent::<GameStatusChanged>(&app);
+        assert_eq!(joins.len(), 1);
+        assert_eq!(joins[0].player, felt(2));
+        assert_eq!(turns.len(), 1);
+        assert_eq!(turns[0].current_player, felt(2));
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(
+            (statuses[0].from, statuses[0].to),
+            (GameStatus::Pending, GameStatus::InProgress)
+        );
+        assert!(sent::<GameWon>(&app).is_empty());
+
+        let won = Game {
+            winner: felt(2),
+            status: GameStatus::Completed,
+            ..joined
+        };
+        sync(&mut app, entity, won);
+        app.update();
+
+        let wins = sent::<GameWon>(&app);
+        assert_eq!(wins.len(), 1);
+        assert_eq!((wins[0].game_id, wins[0].winner), (7, felt(2)));
+        assert!(sent::<TurnChanged>(&app).is_empty());
+    }
+
+    #[test]
+    fn forgets_despawned_games() {
+        let mut app = App::new();
+        app.add_plugins(DojoEventsPlugin);
+        let first = app.world_mut().spawn(pending_game()).id();
+        app.update();
+
+        app.world_mut().despawn(first);
+        // The same game synced again onto a new entity is a new baseline.
+        let started = Game {
+            player_two: felt(2),
+            current_player: felt(2),
+            status: GameStatus::InProgress,
+            ..pending_game()
+        };
+        app.world_mut().spawn(started);
+        app.update();
+
+        assert!(sent::<PlayerJoined>(&app).is_empty());
+        assert!(sent::<GameStatusChanged>(&app).is_empty());
+    }
+
+    #[test]
+    fn reports_claimed_tiles() {
+        let mut app = App::new();
+        app.add_plugins(DojoEventsPlugin);
+        app.world_mut().spawn(Tile {
+            game_id: 7,
+            x: 3,
+            y: 4,
+            owner: felt(1),
+        });
+        app.update();
+
+        let claimed = sent::<TileClaimed>(&app);
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].pos, GridPos { x: 3, y: 4 });
+    }
+}
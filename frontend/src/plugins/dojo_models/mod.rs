@@ -1,11 +1,39 @@
-use bevy::prelude::*;
-use dojo_types::schema::Struct as DojoStruct;
+pub mod decode;
+pub mod events;
 
-use crate::plugins::networking::torii::{BevyFelt, BevyfiedDojoEntity, UpdatedBevyfiedDojoEntity};
+use std::collections::HashMap;
 
-pub struct DojoModelsPlugin;
+use bevy::{ecs::system::EntityCommands, prelude::*};
+pub use dojo_model_derive::DojoModel;
+pub use dojo_types::schema::Struct as DojoStruct;
+
+use decode::ModelDecodeError;
+
+use crate::plugins::networking::torii::{
+    BevyFelt, BevyfiedDojoEntity, EntityIndex, UpdatedBevyfiedDojoEntity,
+};
+pub use crate::rules::GameStatus;
+use crate::rules::{Bitboard, GameState, GridPos, Side};
+
+/// Turns synced Dojo entities into components. The game's own models are
+/// registered under `namespace`; other crates add theirs with
+/// [`RegisterDojoModel::register_dojo_model`].
+pub struct DojoModelsPlugin {
+    pub namespace: String,
+}
+impl Default for DojoModelsPlugin {
+    fn default() -> Self {
+        DojoModelsPlugin {
+            namespace: "grid_guru".to_string(),
+        }
+    }
+}
 impl Plugin for DojoModelsPlugin {
     fn build(&self, app: &mut App) {
+        app.register_dojo_model_in::<Game>(&self.namespace);
+        app.register_dojo_model_in::<Player>(&self.namespace);
+        app.register_dojo_model_in::<Tile>(&self.namespace);
+        app.add_plugins(events::DojoEventsPlugin);
         app.add_observer(convert_to_bevy);
         app.add_observer(list_entities);
         app.register_type::<DojoKey>();
@@ -31,84 +59,125 @@ fn list_entities(
 #[derive(Event, Debug)]
 struct Converted;
 
+/// A component decoded from a Dojo model, usually through
+/// `#[derive(DojoModel)]`.
+pub trait DojoModel:
+    Component + Clone + PartialEq + for<'a> TryFrom<&'a DojoStruct, Error = ModelDecodeError>
+{
+    const NAMESPACE: &'static str;
+    const NAME: &'static str;
+
+    /// The model tag Torii reports, e.g. `grid_guru-Tile`.
+    fn tag() -> String {
+        format!("{}-{}", Self::NAMESPACE, Self::NAME)
+    }
+
+    /// Inserts the component, or updates it in place when its value changed.
+    fn upsert(self, entity: &mut EntityCommands) {
+        let update = self.clone();
+        entity
+            .entry::<Self>()
+            .and_modify(move |mut model| {
+                model.set_if_neq(update);
+            })
+            .or_insert(self);
+    }
+}
+
+type SyncModel = fn(&mut EntityCommands, Option<&DojoStruct>);
+
+/// The models turned into components, by model tag.
+#[derive(Resource, Default)]
+pub struct DojoModelRegistry {
+    models: HashMap<String, SyncModel>,
+}
+impl DojoModelRegistry {
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.models.keys().map(String::as_str)
+    }
+}
+
+pub trait RegisterDojoModel {
+    /// Registers `T` for the model tagged `T::tag()`.
+    fn register_dojo_model<T: DojoModel>(&mut self) -> &mut Self;
+    /// Registers `T` for the model of the same name in `namespace`.
+    fn register_dojo_model_in<T: DojoModel>(&mut self, namespace: &str) -> &mut Self;
+}
+impl RegisterDojoModel for App {
+    fn register_dojo_model<T: DojoModel>(&mut self) -> &mut Self {
+        self.register_dojo_model_in::<T>(T::NAMESPACE)
+    }
+
+    fn register_dojo_model_in<T: DojoModel>(&mut self, namespace: &str) -> &mut Self {
+        let tag = format!("{namespace}-{}", T::NAME);
+        self.init_resource::<DojoModelRegistry>();
+        self.world_mut()
+            .resource_mut::<DojoModelRegistry>()
+            .models
+            .insert(tag, sync_model::<T>);
+        self
+    }
+}
+
+/// Converts the models of the updated entities into components on the same
+/// entities. Entities left without models were deleted and are despawned.
 fn convert_to_bevy(
-    _trigger: Trigger<UpdatedBevyfiedDojoEntity>,
+    trigger: Trigger<UpdatedBevyfiedDojoEntity>,
     mut commands: Commands,
-    query_bevyfied: Query<&BevyfiedDojoEntity>,
-    query_dojokey: Query<(Entity, &DojoKey)>,
+    mut index: ResMut<EntityIndex>,
+    registry: Res<DojoModelRegistry>,
+    query_bevyfied: Query<(&BevyfiedDojoEntity, Has<DojoKey>)>,
 ) {
-    for bevyfied_entity in query_bevyfied.iter() {
-        let key = bevyfied_entity.keys.clone();
-        let bevy_id;
-        if let Some((id, _)) = query_dojokey.iter().find(|(_, k)| k.0 == key) {
-            bevy_id = id;
-        } else {
-            let id = commands.spawn(DojoKey(key)).id();
-            bevy_id = id;
+    for key in trigger.event().keys.iter() {
+        let Some(bevy_id) = index.0.get(key).copied() else {
+            continue;
+        };
+        let Ok((bevyfied_entity, has_key)) = query_bevyfied.get(bevy_id) else {
+            continue;
+        };
+        if bevyfied_entity.models.is_empty() {
+            info!("dojo entity {key:?} was deleted");
+            commands.entity(bevy_id).despawn_recursive();
+            index.0.remove(key);
+            continue;
         }
 
-        let dojo_models = bevyfied_entity.models.clone();
-        for dojo_model in dojo_models.iter() {
-            let struct_name = dojo_model.name.as_str();
-            match struct_name {
-                "grid_guru-Player" => {
-                    let player = Player::from(dojo_model.clone());
-                    let or_player = player.clone();
-                    info!("created bevy native player component: {player:?}");
-                    commands
-                        .entity(bevy_id)
-                        .entry::<Player>()
-                        .and_modify(move |mut p| {
-                            p.game_id = player.game_id;
-                            p.address = player.address;
-                            p.score = player.score;
-                        })
-                        .or_insert(or_player.clone());
-                }
-                "grid_guru-Tile" => {
-                    let tile = Tile::from(dojo_model.clone());
-                    let or_tile = tile.clone();
-                    info!("created bevy native tile component: {tile:?}");
-                    commands
-                        .entity(bevy_id)
-                        .entry::<Tile>()
-                        .and_modify(move |mut t| {
-                            t.game_id = tile.game_id;
-                            t.x = tile.x;
-                            t.y = tile.y;
-                            t.owner = tile.owner;
-                        })
-                        .or_insert(or_tile);
-                }
-                "grid_guru-Game" => {
-                    let game = Game::from(dojo_model.clone());
-                    let or_game = game.clone();
-                    info!("created bevy native game component: {game:?}");
-                    commands
-                        .entity(bevy_id)
-                        .entry::<Game>()
-                        .and_modify(move |mut g| {
-                            g.game_id = game.game_id;
-                            g.player_one = game.player_one;
-                            g.player_two = game.player_two;
-                            g.current_player = game.current_player;
-                            g.winner = game.winner;
-                            g.move_count = game.move_count;
-                            g.status = game.status;
-                        })
-                        .or_insert(or_game);
-                }
-                _ => {}
+        let mut entity = commands.entity(bevy_id);
+        if !has_key {
+            entity.insert(DojoKey(*key));
+        }
+        let models = &bevyfied_entity.models;
+        for (tag, sync) in registry.models.iter() {
+            sync(&mut entity, models.iter().find(|model| &model.name == tag));
+        }
+        for model in models.iter() {
+            if !registry.models.contains_key(&model.name) {
+                debug!("no component registered for model {}", model.name);
             }
         }
     }
     commands.trigger(Converted);
 }
 
+/// Upserts the `T` component from `model`, or removes it when the model is
+/// gone. Models that fail to decode are skipped.
+fn sync_model<T: DojoModel>(entity: &mut EntityCommands, model: Option<&DojoStruct>) {
+    let Some(model) = model else {
+        entity.remove::<T>();
+        return;
+    };
+
+    match T::try_from(model) {
+        Ok(component) => component.upsert(entity),
+        Err(e) => warn!("skipping model of {:?}: {e}", entity.id()),
+    }
+}
+
 #[derive(Component, Debug, Reflect)]
-pub struct DojoKey(BevyFelt);
+pub struct DojoKey(pub BevyFelt);
 
-#[derive(Component, Debug, Reflect, Clone)]
+#[derive(Component, Debug, Reflect, Clone, PartialEq, DojoModel)]
+#[dojo(crate = "crate", namespace = "grid_guru")]
 pub struct Game {
     pub game_id: u128,
     pub player_one: BevyFelt,
@@ -118,86 +187,41 @@ pub struct Game {
     pub move_count: u8,
     pub status: GameStatus,
 }
-impl From<DojoStruct> for Game {
-    fn from(value: DojoStruct) -> Self {
-        let game_id = value.children[0]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_u128()
-            .unwrap();
-        let player_one = value.children[1]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_contract_address()
-            .unwrap()
-            .into();
-        let player_two = value.children[2]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_contract_address()
-            .unwrap()
-            .into();
-        let current_player = value.children[3]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_contract_address()
-            .unwrap()
-            .into();
-        let winner = value.children[4]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_contract_address()
-            .unwrap()
-            .into();
-        let move_count = value.children[5]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_u8()
-            .unwrap();
-        let status_id = value.children[6].ty.as_enum().unwrap().option.unwrap();
-        let status = GameStatus::from(status_id);
-
-        Game {
-            game_id,
-            player_one,
-            player_two,
-            current_player,
-            winner,
-            move_count,
-            status,
-        }
-    }
-}
+impl Game {
+    /// Rebuilds the rules state of this game from its players. `two` is
+    /// `None` until someone joins. Returns `None` if a grid is not a valid
+    /// bitmap.
+    pub fn state(&self, one: &Player, two: Option<&Player>) -> Option<GameState> {
+        let side_of = |address: BevyFelt| {
+            if address == self.player_two && !self.player_two.is_zero() {
+                Some(Side::Two)
+            } else if address == self.player_one && !self.player_one.is_zero() {
+                Some(Side::One)
+            } else {
+                None
+            }
+        };
 
-#[derive(Debug, Clone, Copy, Reflect)]
-pub enum GameStatus {
-    Pending,
-    InProgress,
-    Completed,
-    Draw,
-    Abandoned,
-    TimedOut,
-}
-impl From<u8> for GameStatus {
-    fn from(value: u8) -> Self {
-        match value {
-            0 => GameStatus::Pending,
-            1 => GameStatus::InProgress,
-            2 => GameStatus::Completed,
-            3 => GameStatus::Draw,
-            4 => GameStatus::Abandoned,
-            _ => GameStatus::TimedOut,
-        }
+        let mut state = GameState::from_grids(
+            [
+                one.bitboard()?,
+                two.map_or(Some(Bitboard::EMPTY), Player::bitboard)?,
+            ],
+            [
+                GridPos::new(one.x, one.y),
+                two.map_or(Side::Two.start(), |two| GridPos::new(two.x, two.y)),
+            ],
+            side_of(self.current_player).unwrap_or(Side::One),
+        );
+        state.status = self.status;
+        state.winner = side_of(self.winner);
+        state.move_count = self.move_count;
+        Some(state)
     }
 }
 
-#[derive(Component, Debug, Reflect, Clone)]
+#[derive(Component, Debug, Reflect, Clone, PartialEq, DojoModel)]
+#[dojo(crate = "crate", namespace = "grid_guru")]
 pub struct Player {
     pub game_id: u128,
     pub address: BevyFelt,
@@ -206,98 +230,18 @@ pub struct Player {
     pub x: u8,
     pub y: u8,
 }
-impl From<DojoStruct> for Player {
-    fn from(value: DojoStruct) -> Self {
-        let game_id = value.children[0]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_u128()
-            .unwrap();
-        let address = value.children[1]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_contract_address()
-            .unwrap()
-            .into();
-        let score = value.children[2]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_u8()
-            .unwrap();
-        let grid = value.children[3]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_felt252()
-            .unwrap()
-            .into();
-        let x = value.children[4]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_u8()
-            .unwrap();
-        let y = value.children[5]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_u8()
-            .unwrap();
-
-        Player {
-            game_id,
-            address,
-            score,
-            grid,
-            x,
-            y,
-        }
+impl Player {
+    /// The tiles this player owns, if `grid` is a valid bitmap.
+    pub fn bitboard(&self) -> Option<Bitboard> {
+        Bitboard::from_felt(self.grid.0)
     }
 }
 
-#[derive(Component, Debug, Reflect, Clone)]
+#[derive(Component, Debug, Reflect, Clone, PartialEq, DojoModel)]
+#[dojo(crate = "crate", namespace = "grid_guru")]
 pub struct Tile {
     pub game_id: u128,
     pub x: u8,
     pub y: u8,
     pub owner: BevyFelt,
 }
-impl From<DojoStruct> for Tile {
-    fn from(value: DojoStruct) -> Self {
-        let game_id = value.children[0]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_u128()
-            .unwrap();
-        let x = value.children[1]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_u8()
-            .unwrap();
-        let y = value.children[2]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_u8()
-            .unwrap();
-        let owner = value.children[3]
-            .ty
-            .as_primitive()
-            .unwrap()
-            .as_contract_address()
-            .unwrap()
-            .into();
-
-        Tile {
-            game_id,
-            x,
-            y,
-            owner,
-        }
-    }
-}
@@ -0,0 +1,79 @@
+//! Hot-seat games: two people share this machine and take turns claiming
+//! tiles, with the rules engine standing in for the contracts. Escape goes
+//! back to the menu.
+
+use bevy::prelude::*;
+
+use crate::plugins::backend::{
+    memory::{MemoryBackend, MemoryModel},
+    ActiveBackend, GameIntent, Signer,
+};
+use crate::plugins::networking::starknet_call::ActiveGame;
+use crate::GameState;
+
+pub struct LocalPlugin;
+impl Plugin for LocalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Local), start_local_game);
+        app.add_systems(OnExit(GameState::Local), end_local_game);
+        app.add_systems(Update, leave_local_game.run_if(in_state(GameState::Local)));
+    }
+}
+
+/// The backend and game that were active before the hot-seat game, put
+/// back when it ends.
+#[derive(Resource)]
+struct SuspendedBackend {
+    backend: Option<ActiveBackend>,
+    active_game: Option<ActiveGame>,
+}
+
+/// Switches to a fresh in-memory backend and sets up a game as
+/// `create_game` and `join_game` would leave it.
+fn start_local_game(world: &mut World) {
+    let suspended = SuspendedBackend {
+        backend: world.remove_resource::<ActiveBackend>(),
+        active_game: world.get_resource::<ActiveGame>().copied(),
+    };
+    world.insert_resource(suspended);
+    world.insert_resource(ActiveBackend(Box::new(MemoryBackend::default())));
+
+    world.send_event(GameIntent::CreateGame);
+    world.send_event(GameIntent::JoinGame {
+        game_id: MemoryBackend::FIRST_GAME_ID,
+        signer: Signer::Player,
+    });
+    info!("started a hot-seat game");
+}
+
+/// Despawns the hot-seat game, so the next one starts from a clean board,
+/// and puts the suspended backend back.
+fn end_local_game(world: &mut World) {
+    let models: Vec<Entity> = world
+        .query_filtered::<Entity, With<MemoryModel>>()
+        .iter(world)
+        .collect();
+    for entity in models {
+        world.entity_mut(entity).despawn_recursive();
+    }
+
+    let Some(suspended) = world.remove_resource::<SuspendedBackend>() else {
+        return;
+    };
+    if let Some(backend) = suspended.backend {
+        world.insert_resource(backend);
+    }
+    if let Some(active_game) = suspended.active_game {
+        world.insert_resource(active_game);
+    }
+    info!("left the hot-seat game");
+}
+
+fn leave_local_game(
+    input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+    }
+}
@@ -1,5 +1,8 @@
+pub mod ai;
 // pub mod audio;
+pub mod backend;
 pub mod dojo_models;
+pub mod local;
 pub mod networking;
 pub mod render;
 pub mod screens;
@@ -0,0 +1,134 @@
+use std::{env, fmt, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use starknet::{core::types::Felt, signers::SigningKey};
+
+use super::config::NetworkConfig;
+use super::controller::ControllerAccountConfig;
+
+pub const PASSWORD_ENV: &str = "GRID_GURU_KEYSTORE_PASSWORD";
+
+pub struct AccountsPlugin;
+impl Plugin for AccountsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            setup_accounts.run_if(resource_added::<NetworkConfig>),
+        );
+        app.add_systems(
+            Update,
+            cycle_selected_account.run_if(resource_exists::<SelectedAccount>),
+        );
+    }
+}
+
+/// An account whose private key lives in an encrypted Starknet keystore. With
+/// `controller` set, the key is the owner of a Cartridge Controller at
+/// `address` and transactions go through a controller session.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct AccountConfig {
+    pub name: String,
+    pub address: Felt,
+    pub keystore: PathBuf,
+    /// Environment variable holding the keystore password. Falls back to
+    /// `GRID_GURU_KEYSTORE_PASSWORD`, then to a terminal prompt.
+    pub password_env: Option<String>,
+    pub controller: Option<ControllerAccountConfig>,
+}
+
+#[derive(Debug)]
+pub enum AccountLoadError {
+    Password(String, std::io::Error),
+    Keystore(String, String),
+}
+impl fmt::Display for AccountLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountLoadError::Password(name, e) => {
+                write!(f, "cannot read password for account '{name}': {e}")
+            }
+            AccountLoadError::Keystore(name, e) => {
+                write!(f, "cannot unlock keystore of account '{name}': {e}")
+            }
+        }
+    }
+}
+impl std::error::Error for AccountLoadError {}
+
+impl AccountConfig {
+    /// Decrypts the keystore. This blocks on scrypt and possibly on a
+    /// password prompt, so call it from a blocking task.
+    pub fn unlock(&self) -> Result<SigningKey, AccountLoadError> {
+        let password = self
+            .password_env
+            .iter()
+            .map(String::as_str)
+            .chain([PASSWORD_ENV])
+            .find_map(|var| env::var(var).ok());
+        let password = match password {
+            Some(password) => password,
+            None => rpassword::prompt_password(format!("Password for account '{}': ", self.name))
+                .map_err(|e| AccountLoadError::Password(self.name.clone(), e))?,
+        };
+
+        SigningKey::from_keystore(&self.keystore, &password)
+            .map_err(|e| AccountLoadError::Keystore(self.name.clone(), e.to_string()))
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct AvailableAccounts(pub Vec<AccountConfig>);
+
+#[derive(Resource, Debug, Clone)]
+pub struct SelectedAccount(pub AccountConfig);
+
+fn setup_accounts(mut commands: Commands, config: Res<NetworkConfig>) {
+    if config.accounts.is_empty() {
+        warn!(
+            "profile '{}' has no accounts, transactions are disabled",
+            config.profile
+        );
+        return;
+    }
+
+    for account in config.accounts.iter() {
+        info!(
+            "available account '{}' at {:#x}",
+            account.name, account.address
+        );
+    }
+
+    let selected = config
+        .default_account
+        .as_ref()
+        .and_then(|name| config.accounts.iter().find(|a| &a.name == name))
+        .unwrap_or(&config.accounts[0]);
+
+    commands.insert_resource(SelectedAccount(selected.clone()));
+    commands.insert_resource(AvailableAccounts(config.accounts.clone()));
+}
+
+fn cycle_selected_account(
+    input: Res<ButtonInput<KeyCode>>,
+    accounts: Res<AvailableAccounts>,
+    mut selected: ResMut<SelectedAccount>,
+) {
+    let step = if input.just_pressed(KeyCode::KeyQ) {
+        accounts.0.len() - 1
+    } else if input.just_pressed(KeyCode::KeyW) {
+        1
+    } else {
+        return;
+    };
+
+    let current = accounts
+        .0
+        .iter()
+        .position(|a| a == &selected.0)
+        .unwrap_or(0);
+    let next = &accounts.0[(current + step) % accounts.0.len()];
+    info!("selected account '{}'", next.name);
+    selected.0 = next.clone();
+}
@@ -1,17 +1,573 @@
-pub const RPC_URL: &str = "https://api.cartridge.gg/x/denver-grid/katana";
-pub const TORII_URL: &str = "http://localhost:8080";
-pub const TORII_RELAY_URL: &str = "/ip4/127.0.0.1/tcp/9090";
-
-pub const GAME_WORLD_ADDRESS: &str =
-    "0x000fb6b38cdca072996cdcf55d7f785f78bd930df1e8a5dfd4867987f254b8e7";
-pub const GAME_ACTIONS_CONTRACT_ADDRESS: &str =
-    "0x04e8ca0d54f13a6718001bdbb61e4bcaf69bf0985229a03bdd4db7701a1af9f5";
-
-pub const PLAYER_ONE_ADDRESS: &str =
-    "0x127fd5f1fe78a71f8bcd1fec63e3fe2f0486b6ecd5c86a0466c3a21fa5cfcec";
-pub const PLAYER_ONE_PRIVATE_KEY: &str =
-    "0xc5b2fcab997346f3ea1c00b002ecf6f382c5f9c9659a3894eb783c5320f912";
-pub const PLAYER_TWO_ADDRESS: &str =
-    "0x13d9ee239f33fea4f8785b9e3870ade909e20a9599ae7cd62c1c292b73af1b7";
-pub const PLAYER_TWO_PRIVATE_KEY: &str =
-    "0x1c9053c053edf324aec366a34c6901b1095b07af69495bffec7d7fe21effb1b";
+use std::{collections::HashMap, env, fmt, fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use starknet::{core::types::Felt, providers::Url};
+
+use super::accounts::AccountConfig;
+use super::manifest::{DojoManifest, ManifestError};
+use crate::plugins::backend::BackendKind;
+use crate::rules::ai::Difficulty;
+
+pub const DEFAULT_PROFILE: &str = "dev";
+pub const DEFAULT_CONFIG_PATH: &str = "grid_guru.toml";
+const ENV_PREFIX: &str = "GRID_GURU_";
+
+/// The settings of the selected profile, layered at startup from, in
+/// increasing priority: the built-in profile defaults, a TOML file,
+/// `GRID_GURU_*` environment variables and command line flags. Nothing is
+/// read from the network or the manifest until [`ProfileSettings::network`]
+/// is called, so offline play works without either.
+#[derive(Resource, Debug, Clone)]
+pub struct ProfileSettings {
+    pub profile: String,
+    pub backend: BackendKind,
+    /// Starting level of the AI opponent, off when `None`.
+    pub ai_difficulty: Option<Difficulty>,
+    layer: ProfileLayer,
+}
+
+/// Network settings of a profile. Contract addresses come from the
+/// profile's Dojo manifest unless they are set explicitly.
+#[derive(Resource, Debug, Clone)]
+pub struct NetworkConfig {
+    pub profile: String,
+    pub rpc_url: Url,
+    /// Checked to be a URL, kept as the string the Torii client takes.
+    pub torii_url: String,
+    pub torii_relay_url: String,
+    pub world_address: Felt,
+    pub actions_address: Felt,
+    pub manifest: Option<DojoManifest>,
+    pub accounts: Vec<AccountConfig>,
+    pub default_account: Option<String>,
+    /// Name of the account the AI opponent plays with on chain.
+    pub ai_account: Option<String>,
+    /// Applied to both the estimated gas amount and gas price of every
+    /// transaction.
+    pub fee_multiplier: f64,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    UnknownProfile(String),
+    MissingValue(String, &'static str),
+    InvalidFelt(&'static str, String),
+    InvalidNumber(&'static str, String),
+    MissingFlagValue(String),
+    UnknownFlag(String),
+    UnknownKey(String),
+    Invalid(&'static str, String),
+    Manifest(ManifestError),
+}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "cannot read {}: {e}", path.display()),
+            ConfigError::Parse(path, e) => write!(f, "cannot parse {}: {e}", path.display()),
+            ConfigError::UnknownProfile(name) => write!(f, "unknown profile '{name}'"),
+            ConfigError::MissingValue(profile, key) => {
+                write!(f, "profile '{profile}' has no value for '{key}'")
+            }
+            ConfigError::InvalidFelt(key, value) => {
+                write!(f, "'{key}' is not a valid felt: {value}")
+            }
+            ConfigError::InvalidNumber(key, value) => {
+                write!(f, "'{key}' is not a valid number: {value}")
+            }
+            ConfigError::MissingFlagValue(flag) => write!(f, "missing value for --{flag}"),
+            ConfigError::UnknownFlag(flag) => write!(f, "unknown flag --{flag}"),
+            ConfigError::UnknownKey(key) => write!(f, "unknown setting '{key}'"),
+            ConfigError::Invalid(key, message) => write!(f, "invalid '{key}': {message}"),
+            ConfigError::Manifest(e) => write!(f, "{e}"),
+        }
+    }
+}
+impl std::error::Error for ConfigError {}
+
+/// One layer of settings for a profile. Every layer only overrides the
+/// values it actually sets.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+struct ProfileLayer {
+    backend: Option<String>,
+    rpc_url: Option<String>,
+    torii_url: Option<String>,
+    torii_relay_url: Option<String>,
+    manifest: Option<String>,
+    world_address: Option<String>,
+    actions_address: Option<String>,
+    accounts: Option<Vec<AccountConfig>>,
+    account: Option<String>,
+    ai_account: Option<String>,
+    ai_difficulty: Option<String>,
+    fee_multiplier: Option<f64>,
+}
+impl ProfileLayer {
+    fn merge(&mut self, other: ProfileLayer) {
+        macro_rules! take {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field;
+                })*
+            };
+        }
+        take!(
+            backend,
+            rpc_url,
+            torii_url,
+            torii_relay_url,
+            manifest,
+            world_address,
+            actions_address,
+            accounts,
+            account,
+            ai_account,
+            ai_difficulty,
+            fee_multiplier
+        );
+    }
+
+    fn set(&mut self, key: &'static str, value: String) -> Result<(), ConfigError> {
+        let field = match key {
+            "backend" => &mut self.backend,
+            "rpc_url" => &mut self.rpc_url,
+            "torii_url" => &mut self.torii_url,
+            "torii_relay_url" => &mut self.torii_relay_url,
+            "manifest" => &mut self.manifest,
+            "world_address" => &mut self.world_address,
+            "actions_address" => &mut self.actions_address,
+            "account" => &mut self.account,
+            "ai_account" => &mut self.ai_account,
+            "ai_difficulty" => &mut self.ai_difficulty,
+            "fee_multiplier" => {
+                let multiplier = value
+                    .parse()
+                    .map_err(|_| ConfigError::InvalidNumber(key, value))?;
+                self.fee_multiplier = Some(multiplier);
+                return Ok(());
+            }
+            _ => return Err(ConfigError::UnknownKey(key.to_string())),
+        };
+        *field = Some(value);
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    profile: Option<String>,
+    #[serde(default)]
+    profiles: HashMap<String, ProfileLayer>,
+}
+
+/// Keys that may be overridden from the environment or the command line.
+const OVERRIDABLE_KEYS: [&str; 11] = [
+    "backend",
+    "rpc_url",
+    "torii_url",
+    "torii_relay_url",
+    "manifest",
+    "world_address",
+    "actions_address",
+    "account",
+    "ai_account",
+    "ai_difficulty",
+    "fee_multiplier",
+];
+const DEFAULT_FEE_MULTIPLIER: f64 = 1.5;
+
+fn default_profiles() -> HashMap<String, ProfileLayer> {
+    let dev = ProfileLayer {
+        rpc_url: Some("https://api.cartridge.gg/x/denver-grid/katana".into()),
+        torii_url: Some("http://localhost:8080".into()),
+        torii_relay_url: Some("/ip4/127.0.0.1/tcp/9090".into()),
+        manifest: Some("../contracts/manifest_dev.json".into()),
+        ..default()
+    };
+    // There is no public Torii for the sepolia world, so its URLs have to
+    // be configured.
+    let sepolia = ProfileLayer {
+        rpc_url: Some("https://api.cartridge.gg/x/starknet/sepolia".into()),
+        manifest: Some("../contracts/manifest_sepolia.json".into()),
+        ..default()
+    };
+
+    HashMap::from([("dev".to_string(), dev), ("sepolia".to_string(), sepolia)])
+}
+
+/// Flags that are not profile settings.
+const CLI_ONLY_FLAGS: [&str; 2] = ["config", "profile"];
+
+/// Parses `--flag value` and `--flag=value` pairs, ignoring arguments that
+/// are not flags.
+fn parse_cli_flags(
+    args: impl Iterator<Item = String>,
+) -> Result<HashMap<String, String>, ConfigError> {
+    let mut flags = HashMap::new();
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            continue;
+        };
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => match args.next_if(|next| !next.starts_with("--")) {
+                Some(value) => (flag.to_string(), value),
+                None => return Err(ConfigError::MissingFlagValue(flag.to_string())),
+            },
+        };
+        let name = name.replace('-', "_");
+        if !CLI_ONLY_FLAGS.contains(&name.as_str()) && !OVERRIDABLE_KEYS.contains(&name.as_str()) {
+            return Err(ConfigError::UnknownFlag(name));
+        }
+        flags.insert(name, value);
+    }
+    Ok(flags)
+}
+
+fn parse_url(key: &'static str, value: &str) -> Result<Url, ConfigError> {
+    Url::parse(value).map_err(|e| ConfigError::Invalid(key, format!("{value}: {e}")))
+}
+
+/// The environment variable that sets `key`.
+fn env_name(key: &str) -> String {
+    format!("{ENV_PREFIX}{}", key.to_uppercase())
+}
+
+fn parse_felt(key: &'static str, value: &str) -> Result<Felt, ConfigError> {
+    Felt::from_hex(value).map_err(|_| ConfigError::InvalidFelt(key, value.to_string()))
+}
+
+impl ProfileSettings {
+    pub fn load() -> Result<Self, ConfigError> {
+        let cli = parse_cli_flags(env::args().skip(1))?;
+        let env_var = |key: &str| env::var(env_name(key)).ok();
+
+        let config_path = cli
+            .get("config")
+            .cloned()
+            .or_else(|| env_var("config"))
+            .map(PathBuf::from);
+        let file = match &config_path {
+            Some(path) => Self::read_file(path)?,
+            None => {
+                let path = PathBuf::from(DEFAULT_CONFIG_PATH);
+                if path.exists() {
+                    Self::read_file(&path)?
+                } else {
+                    ConfigFile::default()
+                }
+            }
+        };
+
+        Self::layered(file, |name| env::var(name).ok(), &cli)
+    }
+
+    /// Layers the built-in defaults, `file`, the environment variables
+    /// `env` looks up and the command line flags.
+    fn layered(
+        file: ConfigFile,
+        env: impl Fn(&str) -> Option<String>,
+        cli: &HashMap<String, String>,
+    ) -> Result<Self, ConfigError> {
+        let env_var = |key: &str| env(&env_name(key));
+        let profile = cli
+            .get("profile")
+            .cloned()
+            .or_else(|| env_var("profile"))
+            .or(file.profile)
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+
+        let mut profiles = default_profiles();
+        for (name, layer) in file.profiles {
+            profiles.entry(name).or_default().merge(layer);
+        }
+        let mut layer = profiles
+            .remove(&profile)
+            .ok_or_else(|| ConfigError::UnknownProfile(profile.clone()))?;
+
+        for key in OVERRIDABLE_KEYS {
+            if let Some(value) = env_var(key) {
+                layer.set(key, value)?;
+            }
+        }
+        for key in OVERRIDABLE_KEYS {
+            if let Some(value) = cli.get(key) {
+                layer.set(key, value.clone())?;
+            }
+        }
+
+        let backend = match &layer.backend {
+            Some(value) => value
+                .parse()
+                .map_err(|e| ConfigError::Invalid("backend", e))?,
+            None => BackendKind::Starknet,
+        };
+        let ai_difficulty = match &layer.ai_difficulty {
+            Some(value) => Some(
+                value
+                    .parse()
+                    .map_err(|e| ConfigError::Invalid("ai_difficulty", e))?,
+            ),
+            None => None,
+        };
+
+        Ok(ProfileSettings {
+            profile,
+            backend,
+            ai_difficulty,
+            layer,
+        })
+    }
+
+    fn read_file(path: &PathBuf) -> Result<ConfigFile, ConfigError> {
+        let contents = fs::read_to_string(path).map_err(|e| ConfigError::Read(path.clone(), e))?;
+        toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.clone(), e))
+    }
+
+    /// Resolves the network settings, reading the manifest if the profile
+    /// has one.
+    pub fn network(&self) -> Result<NetworkConfig, ConfigError> {
+        NetworkConfig::resolve(self.profile.clone(), self.layer.clone())
+    }
+}
+
+impl NetworkConfig {
+    fn resolve(profile: String, layer: ProfileLayer) -> Result<Self, ConfigError> {
+        let require = |value: Option<String>, key: &'static str| {
+            value.ok_or_else(|| ConfigError::MissingValue(profile.clone(), key))
+        };
+        let rpc_url = parse_url("rpc_url", &require(layer.rpc_url, "rpc_url")?)?;
+        let torii_url = require(layer.torii_url, "torii_url")?;
+        parse_url("torii_url", &torii_url)?;
+        // Below one the gas bounds undercut the estimate and every
+        // transaction fails.
+        let fee_multiplier = layer.fee_multiplier.unwrap_or(DEFAULT_FEE_MULTIPLIER);
+        if !fee_multiplier.is_finite() || fee_multiplier < 1.0 {
+            return Err(ConfigError::Invalid(
+                "fee_multiplier",
+                format!("{fee_multiplier} is not a number of at least 1.0"),
+            ));
+        }
+        let manifest = match &layer.manifest {
+            Some(path) => Some(DojoManifest::load(path).map_err(ConfigError::Manifest)?),
+            None => None,
+        };
+        let address = |value: Option<String>,
+                       from_manifest: Option<Felt>,
+                       key: &'static str|
+         -> Result<Felt, ConfigError> {
+            match (value, from_manifest) {
+                (Some(value), _) => parse_felt(key, &value),
+                (None, Some(felt)) => Ok(felt),
+                (None, None) => Err(ConfigError::MissingValue(profile.clone(), key)),
+            }
+        };
+
+        Ok(NetworkConfig {
+            rpc_url,
+            torii_url,
+            torii_relay_url: require(layer.torii_relay_url, "torii_relay_url")?,
+            world_address: address(
+                layer.world_address,
+                manifest.as_ref().map(|m| m.world_address),
+                "world_address",
+            )?,
+            actions_address: address(
+                layer.actions_address,
+                manifest.as_ref().map(|m| m.actions_address),
+                "actions_address",
+            )?,
+            manifest,
+            accounts: layer.accounts.unwrap_or_default(),
+            default_account: layer.account,
+            ai_account: layer.ai_account,
+            fee_multiplier,
+            profile,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(toml: &str) -> ConfigFile {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn flags(args: &[&str]) -> Result<HashMap<String, String>, ConfigError> {
+        parse_cli_flags(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    const FILE: &str = r#"
+        [profiles.dev]
+        rpc_url = "http://file:5050"
+        torii_url = "http://file:8080"
+        fee_multiplier = 2.0
+    "#;
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let settings = ProfileSettings::layered(
+            file(FILE),
+            env(&[
+                ("GRID_GURU_RPC_URL", "http://env:5050"),
+                ("GRID_GURU_TORII_URL", "http://env:8080"),
+            ]),
+            &flags(&["--rpc-url", "http://cli:5050"]).unwrap(),
+        )
+        .unwrap();
+        let layer = &settings.layer;
+
+        assert_eq!(layer.rpc_url.as_deref(), Some("http://cli:5050"));
+        assert_eq!(layer.torii_url.as_deref(), Some("http://env:8080"));
+        assert_eq!(layer.fee_multiplier, Some(2.0));
+        assert_eq!(
+            layer.torii_relay_url.as_deref(),
+            Some("/ip4/127.0.0.1/tcp/9090")
+        );
+    }
+
+    #[test]
+    fn selects_the_profile_by_precedence() {
+        let profile = |env_vars: &[(&str, &str)], args: &[&str]| {
+            ProfileSettings::layered(
+                file("profile = \"sepolia\""),
+                env(env_vars),
+                &flags(args).unwrap(),
+            )
+            .unwrap()
+            .profile
+        };
+
+        assert_eq!(profile(&[], &[]), "sepolia");
+        assert_eq!(profile(&[("GRID_GURU_PROFILE", "dev")], &[]), "dev");
+        assert_eq!(
+            profile(&[("GRID_GURU_PROFILE", "dev")], &["--profile", "sepolia"]),
+            "sepolia"
+        );
+        assert_eq!(
+            ProfileSettings::layered(ConfigFile::default(), env(&[]), &HashMap::new())
+                .unwrap()
+                .profile,
+            DEFAULT_PROFILE
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let error = ProfileSettings::layered(
+            ConfigFile::default(),
+            env(&[("GRID_GURU_BACKEND", "solana")]),
+            &HashMap::new(),
+        )
+        .unwrap_err();
+        assert!(matches!(error, ConfigError::Invalid("backend", _)));
+
+        let error = ProfileSettings::layered(
+            ConfigFile::default(),
+            env(&[]),
+            &flags(&["--profile", "mainnet"]).unwrap(),
+        )
+        .unwrap_err();
+        assert!(matches!(error, ConfigError::UnknownProfile(name) if name == "mainnet"));
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        let network = |env_vars: &[(&str, &str)], args: &[&str]| {
+            ProfileSettings::layered(ConfigFile::default(), env(env_vars), &flags(args).unwrap())
+                .unwrap()
+                .network()
+        };
+
+        let error = network(&[], &["--rpc-url", "localhost 5050"]).unwrap_err();
+        assert!(matches!(error, ConfigError::Invalid("rpc_url", _)));
+
+        let error = network(&[("GRID_GURU_TORII_URL", "http//localhost:8080")], &[]).unwrap_err();
+        assert!(matches!(error, ConfigError::Invalid("torii_url", _)));
+    }
+
+    #[test]
+    fn rejects_fee_multipliers_below_one() {
+        for multiplier in ["0", "-2", "0.5", "NaN", "inf"] {
+            let error = ProfileSettings::layered(
+                ConfigFile::default(),
+                env(&[]),
+                &flags(&["--fee-multiplier", multiplier]).unwrap(),
+            )
+            .unwrap()
+            .network()
+            .unwrap_err();
+
+            assert!(
+                matches!(error, ConfigError::Invalid("fee_multiplier", _)),
+                "{multiplier}: {error}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_flags() {
+        let parsed = flags(&[
+            "--profile=sepolia",
+            "play",
+            "--fee-multiplier",
+            "1.2",
+            "--account",
+            "player_two",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            parsed,
+            HashMap::from([
+                ("profile".to_string(), "sepolia".to_string()),
+                ("fee_multiplier".to_string(), "1.2".to_string()),
+                ("account".to_string(), "player_two".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_incomplete_flags() {
+        assert!(matches!(
+            flags(&["--rpc", "http://localhost:5050"]),
+            Err(ConfigError::UnknownFlag(flag)) if flag == "rpc"
+        ));
+        assert!(matches!(
+            flags(&["--account", "--profile", "dev"]),
+            Err(ConfigError::MissingFlagValue(flag)) if flag == "account"
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let error = ProfileLayer::default()
+            .set("rpc", "http://localhost:5050".to_string())
+            .unwrap_err();
+
+        assert!(matches!(error, ConfigError::UnknownKey(key) if key == "rpc"));
+    }
+
+    #[test]
+    fn sepolia_has_no_local_torii() {
+        let sepolia = default_profiles().remove("sepolia").unwrap();
+
+        assert_eq!(sepolia.torii_url, None);
+        assert_eq!(sepolia.torii_relay_url, None);
+    }
+}
@@ -1,33 +1,181 @@
-use account_sdk::controller::Controller;
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use account_sdk::{
+    account::session::{account::SessionAccount, policy::Policy},
+    controller::Controller,
+    signers::{Owner, Signer},
+};
 use bevy::prelude::*;
+use serde::Deserialize;
 use starknet::{
-    core::types::Felt,
-    providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider, Url},
+    core::{types::Felt, utils::get_selector_from_name},
+    providers::Url,
     signers::SigningKey,
 };
 
-use super::config::{PLAYER_ONE_PRIVATE_KEY, RPC_URL};
+pub const DEFAULT_APP_ID: &str = "https://app.gridguru.xyz";
+const DEFAULT_SESSION_HOURS: u64 = 24;
 
 pub struct ControllerPlugin;
 impl Plugin for ControllerPlugin {
-    fn build(&self, app: &mut App) {}
-}
-
-async fn start_controller() {
-    let owner = SigningKey::from_secret_scalar(Felt::from_hex_unchecked(PLAYER_ONE_PRIVATE_KEY));
-    let provider = JsonRpcClient::new(HttpTransport::new(Url::parse(RPC_URL).unwrap()));
-    let chain_id = provider.chain_id().await.unwrap();
-    let app_id = "https://app.gridguru.xyz";
-    let class_hash = "0x1";
-
-    // let username = "player_one";
-    // let controller = Controller::new(
-    //     app_id.to_string(),
-    //     username.to_string(),
-    //     Felt::from_hex_unchecked(class_hash),
-    //     Url::parse(RPC_URL).unwrap(),
-    //     owner,
-    //     provider,
-    //     chain_id,
-    // );
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SessionPolicies {
+            methods: vec!["create_game", "join_game", "claim_tile"],
+        });
+    }
+}
+
+/// The `actions` entrypoints a controller session is allowed to call
+/// without asking the owner again.
+#[derive(Resource, Debug, Clone)]
+pub struct SessionPolicies {
+    pub methods: Vec<&'static str>,
+}
+
+/// Marks an account as a Cartridge Controller. The account's keystore then
+/// holds the controller owner key rather than an account key.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ControllerAccountConfig {
+    pub username: String,
+    pub class_hash: Felt,
+    pub app_id: Option<String>,
+    pub session_hours: Option<u64>,
+}
+
+#[derive(Debug)]
+pub enum ControllerError {
+    InvalidMethod(&'static str),
+    Session(String),
+}
+impl fmt::Display for ControllerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControllerError::InvalidMethod(method) => {
+                write!(f, "cannot compute selector of '{method}'")
+            }
+            ControllerError::Session(e) => write!(f, "cannot register controller session: {e}"),
+        }
+    }
+}
+impl std::error::Error for ControllerError {}
+
+impl SessionPolicies {
+    fn to_policies(&self, actions_address: Felt) -> Result<Vec<Policy>, ControllerError> {
+        self.methods
+            .iter()
+            .map(|method| {
+                get_selector_from_name(method)
+                    .map(|selector| Policy::new_call(actions_address, selector))
+                    .map_err(|_| ControllerError::InvalidMethod(method))
+            })
+            .collect()
+    }
+}
+
+/// Builds the controller at `address` owned by `owner` and registers a
+/// session restricted to `policies` on the `actions` contract.
+pub async fn start_controller(
+    config: &ControllerAccountConfig,
+    owner: SigningKey,
+    address: Felt,
+    rpc_url: &Url,
+    chain_id: Felt,
+    actions_address: Felt,
+    policies: &SessionPolicies,
+) -> Result<SessionAccount, ControllerError> {
+    let app_id = config.app_id.as_deref().unwrap_or(DEFAULT_APP_ID);
+
+    let mut controller = Controller::new(
+        app_id.to_string(),
+        config.username.clone(),
+        config.class_hash,
+        rpc_url.clone(),
+        Owner::Signer(Signer::Starknet(owner)),
+        address,
+        chain_id,
+    );
+
+    let session_hours = config.session_hours.unwrap_or(DEFAULT_SESSION_HOURS);
+    let expires_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+        + session_hours * 3600;
+
+    controller
+        .create_session(policies.to_policies(actions_address)?, expires_at)
+        .await
+        .map_err(|e| ControllerError::Session(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet::{
+        accounts::Account,
+        core::types::{Call, ExecutionResult},
+        providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider},
+    };
+
+    use super::*;
+
+    fn env_felt(name: &str) -> Felt {
+        let value = std::env::var(name).unwrap_or_else(|_| panic!("set {name} to run this test"));
+        Felt::from_hex(&value).unwrap_or_else(|e| panic!("{name}: {e}"))
+    }
+
+    /// Needs a Katana started with `katana --dev --cartridge.controllers`,
+    /// the world migrated to it and a deployed controller, described by the
+    /// `GRID_GURU_TEST_*` variables read below.
+    #[tokio::test]
+    #[ignore = "needs a local Katana with a deployed controller"]
+    async fn runs_a_session_call_against_katana() {
+        let rpc_url = std::env::var("GRID_GURU_TEST_RPC_URL")
+            .unwrap_or_else(|_| "http://localhost:5050".to_string());
+        let rpc_url = Url::parse(&rpc_url).unwrap();
+        let provider = JsonRpcClient::new(HttpTransport::new(rpc_url.clone()));
+        let chain_id = provider.chain_id().await.unwrap();
+        let actions_address = env_felt("GRID_GURU_TEST_ACTIONS_ADDRESS");
+        let config = ControllerAccountConfig {
+            username: std::env::var("GRID_GURU_TEST_CONTROLLER_USERNAME")
+                .unwrap_or_else(|_| "player_one".to_string()),
+            class_hash: env_felt("GRID_GURU_TEST_CONTROLLER_CLASS_HASH"),
+            app_id: None,
+            session_hours: Some(1),
+        };
+        let mut app = App::new();
+        app.add_plugins(ControllerPlugin);
+        let policies = app.world().resource::<SessionPolicies>().clone();
+
+        let session = start_controller(
+            &config,
+            SigningKey::from_secret_scalar(env_felt("GRID_GURU_TEST_CONTROLLER_OWNER_KEY")),
+            env_felt("GRID_GURU_TEST_CONTROLLER_ADDRESS"),
+            &rpc_url,
+            chain_id,
+            actions_address,
+            &policies,
+        )
+        .await
+        .unwrap();
+        let call = Call {
+            to: actions_address,
+            selector: get_selector_from_name("create_game").unwrap(),
+            calldata: vec![],
+        };
+        let sent = session.execute_v3(vec![call]).send().await.unwrap();
+
+        // Katana mines every transaction right away in dev mode.
+        let receipt = provider
+            .get_transaction_receipt(sent.transaction_hash)
+            .await
+            .unwrap();
+        assert_eq!(
+            receipt.receipt.execution_result(),
+            &ExecutionResult::Succeeded
+        );
+    }
 }
@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use starknet::{
+    core::types::Felt,
+    providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider},
+};
+use tokio::sync::oneshot;
+
+use super::config::NetworkConfig;
+use super::manifest::{deployed_class_hash, ACTIONS_CONTRACT_TAG};
+use super::tokio::{TokioRuntimeResource, TokioRuntimeState};
+use super::NetworkFailure;
+
+/// Checks once, before anything talks to the world, that the configured
+/// world and actions contract are deployed on the chain behind `rpc_url`,
+/// and match the manifest when there is one. Torii and the command thread
+/// only start from a [`VerifiedChain`].
+pub struct DeploymentPlugin;
+impl Plugin for DeploymentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(TokioRuntimeState::Ready), spawn_deployment_check);
+        app.add_systems(
+            Update,
+            await_deployment_check.run_if(resource_exists::<DeploymentCheck>),
+        );
+    }
+}
+
+/// The chain the configured world was found on.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct VerifiedChain {
+    pub chain_id: Felt,
+}
+
+#[derive(Resource)]
+struct DeploymentCheck(oneshot::Receiver<Result<Felt, String>>);
+
+fn spawn_deployment_check(
+    mut commands: Commands,
+    rt: Res<TokioRuntimeResource>,
+    config: Res<NetworkConfig>,
+) {
+    let (tx, rx) = oneshot::channel();
+    let config = config.clone();
+    let _ = rt.0.spawn(async move {
+        let _ = tx.send(check_deployment(&config).await);
+    });
+    commands.insert_resource(DeploymentCheck(rx));
+}
+
+/// Returns the chain id once the world checks out.
+async fn check_deployment(config: &NetworkConfig) -> Result<Felt, String> {
+    let provider = JsonRpcClient::new(HttpTransport::new(config.rpc_url.clone()));
+    let chain_id = provider
+        .chain_id()
+        .await
+        .map_err(|e| format!("cannot reach {}: {e}", config.rpc_url))?;
+
+    let checked = match &config.manifest {
+        Some(manifest) => {
+            manifest
+                .verify(&provider, config.world_address, config.actions_address)
+                .await
+        }
+        None => {
+            let contracts = [
+                ("world", config.world_address),
+                (ACTIONS_CONTRACT_TAG, config.actions_address),
+            ];
+            let mut checked = Ok(());
+            for (tag, address) in contracts {
+                if let Err(e) = deployed_class_hash(&provider, tag, address).await {
+                    checked = Err(e);
+                    break;
+                }
+            }
+            checked
+        }
+    };
+    checked.map_err(|e| format!("the world does not match {}: {e}", config.rpc_url))?;
+    Ok(chain_id)
+}
+
+fn await_deployment_check(mut commands: Commands, mut check: ResMut<DeploymentCheck>) {
+    let checked = match check.0.try_recv() {
+        Ok(checked) => checked,
+        Err(oneshot::error::TryRecvError::Empty) => return,
+        Err(oneshot::error::TryRecvError::Closed) => {
+            Err("the deployment check stopped".to_string())
+        }
+    };
+    commands.remove_resource::<DeploymentCheck>();
+
+    match checked {
+        Ok(chain_id) => {
+            info!("world verified on chain {chain_id:#x}");
+            commands.insert_resource(VerifiedChain { chain_id });
+        }
+        Err(reason) => {
+            error!("not connecting to the world: {reason}");
+            commands.insert_resource(NetworkFailure(reason));
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+use serde::Deserialize;
+use starknet::{
+    core::{
+        types::{BlockId, BlockTag, Felt, FunctionCall, StarknetError},
+        utils::get_selector_from_name,
+    },
+    providers::{Provider, ProviderError},
+};
+
+pub const ACTIONS_CONTRACT_TAG: &str = "grid_guru-actions";
+/// Index of `Resource::Model` in what the world's `resource` returns.
+const RESOURCE_MODEL: Felt = Felt::ZERO;
+
+/// Addresses and selectors resolved from a `sozo migrate` manifest.
+#[derive(Debug, Clone)]
+pub struct DojoManifest {
+    pub world_address: Felt,
+    pub world_class_hash: Felt,
+    pub actions_address: Felt,
+    pub actions_class_hash: Felt,
+    pub model_selectors: HashMap<String, Felt>,
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Read(String, std::io::Error),
+    Parse(String, serde_json::Error),
+    InvalidFelt(String, String),
+    MissingContract(&'static str),
+    NotDeployed {
+        tag: String,
+        address: Felt,
+    },
+    ClassHashMismatch {
+        tag: String,
+        manifest: Felt,
+        chain: Felt,
+    },
+    ModelNotRegistered(String),
+    Provider(ProviderError),
+}
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Read(path, e) => write!(f, "cannot read manifest {path}: {e}"),
+            ManifestError::Parse(path, e) => write!(f, "cannot parse manifest {path}: {e}"),
+            ManifestError::InvalidFelt(field, value) => {
+                write!(f, "manifest field '{field}' is not a valid felt: {value}")
+            }
+            ManifestError::MissingContract(tag) => {
+                write!(f, "manifest has no contract tagged '{tag}'")
+            }
+            ManifestError::NotDeployed { tag, address } => write!(
+                f,
+                "'{tag}' is not deployed at {address:#x} on this chain, \
+                 is the manifest from a different network?"
+            ),
+            ManifestError::ClassHashMismatch {
+                tag,
+                manifest,
+                chain,
+            } => write!(
+                f,
+                "'{tag}' has class hash {chain:#x} on chain but {manifest:#x} in the manifest, \
+                 migrate again or pick the matching manifest"
+            ),
+            ManifestError::ModelNotRegistered(tag) => {
+                write!(f, "model '{tag}' is not registered in the world")
+            }
+            ManifestError::Provider(e) => write!(f, "cannot verify manifest: {e}"),
+        }
+    }
+}
+impl std::error::Error for ManifestError {}
+
+#[derive(Deserialize)]
+struct RawManifest {
+    world: RawContract,
+    contracts: Vec<RawContract>,
+    models: Vec<RawModel>,
+}
+
+#[derive(Deserialize)]
+struct RawContract {
+    #[serde(default)]
+    tag: String,
+    address: String,
+    class_hash: String,
+}
+
+#[derive(Deserialize)]
+struct RawModel {
+    tag: String,
+    selector: String,
+}
+
+fn parse_felt(field: &str, value: &str) -> Result<Felt, ManifestError> {
+    Felt::from_hex(value).map_err(|_| ManifestError::InvalidFelt(field.into(), value.into()))
+}
+
+impl DojoManifest {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let path = path.as_ref();
+        let display = path.display().to_string();
+        let contents =
+            fs::read_to_string(path).map_err(|e| ManifestError::Read(display.clone(), e))?;
+        let raw: RawManifest =
+            serde_json::from_str(&contents).map_err(|e| ManifestError::Parse(display, e))?;
+
+        let actions = raw
+            .contracts
+            .iter()
+            .find(|c| c.tag == ACTIONS_CONTRACT_TAG)
+            .ok_or(ManifestError::MissingContract(ACTIONS_CONTRACT_TAG))?;
+
+        let mut model_selectors = HashMap::new();
+        for model in raw.models.iter() {
+            model_selectors.insert(model.tag.clone(), parse_felt(&model.tag, &model.selector)?);
+        }
+
+        Ok(DojoManifest {
+            world_address: parse_felt("world.address", &raw.world.address)?,
+            world_class_hash: parse_felt("world.class_hash", &raw.world.class_hash)?,
+            actions_address: parse_felt(&actions.tag, &actions.address)?,
+            actions_class_hash: parse_felt(&actions.tag, &actions.class_hash)?,
+            model_selectors,
+        })
+    }
+
+    pub fn model_selector(&self, tag: &str) -> Option<Felt> {
+        self.model_selectors.get(tag).copied()
+    }
+
+    /// Checks that the world and the actions contract at the given
+    /// addresses, which may override the manifest's, run the manifest's
+    /// classes on the chain behind `provider`, and that the world has every
+    /// model of the manifest registered.
+    pub async fn verify<P: Provider>(
+        &self,
+        provider: &P,
+        world_address: Felt,
+        actions_address: Felt,
+    ) -> Result<(), ManifestError> {
+        let expected = [
+            ("world", world_address, self.world_class_hash),
+            (
+                ACTIONS_CONTRACT_TAG,
+                actions_address,
+                self.actions_class_hash,
+            ),
+        ];
+        for (tag, address, class_hash) in expected {
+            let chain = deployed_class_hash(provider, tag, address).await?;
+            if chain != class_hash {
+                return Err(ManifestError::ClassHashMismatch {
+                    tag: tag.to_string(),
+                    manifest: class_hash,
+                    chain,
+                });
+            }
+        }
+
+        let mut models: Vec<_> = self.model_selectors.iter().collect();
+        models.sort();
+        for (tag, selector) in models {
+            let resource = provider
+                .call(
+                    FunctionCall {
+                        contract_address: world_address,
+                        entry_point_selector: get_selector_from_name("resource").unwrap(),
+                        calldata: vec![*selector],
+                    },
+                    BlockId::Tag(BlockTag::Latest),
+                )
+                .await
+                .map_err(ManifestError::Provider)?;
+            if resource.first() != Some(&RESOURCE_MODEL) {
+                return Err(ManifestError::ModelNotRegistered(tag.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The class hash of the contract at `address`, or why there is none.
+pub async fn deployed_class_hash<P: Provider>(
+    provider: &P,
+    tag: &str,
+    address: Felt,
+) -> Result<Felt, ManifestError> {
+    match provider
+        .get_class_hash_at(BlockId::Tag(BlockTag::Latest), address)
+        .await
+    {
+        Ok(class_hash) => Ok(class_hash),
+        Err(ProviderError::StarknetError(StarknetError::ContractNotFound)) => {
+            Err(ManifestError::NotDeployed {
+                tag: tag.to_string(),
+                address,
+            })
+        }
+        Err(e) => Err(ManifestError::Provider(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEV_MANIFEST: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../contracts/manifest_dev.json"
+    );
+
+    #[test]
+    fn resolves_the_shipped_dev_manifest() {
+        let manifest = DojoManifest::load(DEV_MANIFEST).unwrap();
+        let felt = |hex| Felt::from_hex(hex).unwrap();
+
+        assert_eq!(
+            manifest.world_address,
+            felt("0xfb6b38cdca072996cdcf55d7f785f78bd930df1e8a5dfd4867987f254b8e7")
+        );
+        assert_eq!(
+            manifest.actions_address,
+            felt("0x4e8ca0d54f13a6718001bdbb61e4bcaf69bf0985229a03bdd4db7701a1af9f5")
+        );
+        assert_eq!(
+            manifest.model_selector("grid_guru-Game"),
+            Some(felt(
+                "0x417804f259f3de4f0a2396c529df4812ddef14520594f074b5b95cf0e28c48d"
+            ))
+        );
+        assert_eq!(
+            manifest.model_selector("grid_guru-Player"),
+            Some(felt(
+                "0x3d3b40383444288594e161b8e6ed08a4f6b807387455b9b320a38cee8633c76"
+            ))
+        );
+        assert_eq!(
+            manifest.model_selector("grid_guru-Tile"),
+            Some(felt(
+                "0x7559f30b9c196ff040d00cefd69545652def9d0525dce5695322afcb13dc632"
+            ))
+        );
+        assert_eq!(manifest.model_selector("grid_guru-Unknown"), None);
+    }
+}
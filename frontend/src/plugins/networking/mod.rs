@@ -1,19 +1,65 @@
+pub mod accounts;
 pub mod config;
 pub mod controller;
+pub mod deployment;
+pub mod manifest;
+pub mod queue;
 pub mod starknet_call;
 pub mod tokio;
 pub mod torii;
+pub mod transactions;
 
+pub use crate::rules::errors;
+
+use accounts::AccountsPlugin;
 use bevy::prelude::*;
+use config::{NetworkConfig, ProfileSettings};
+use controller::ControllerPlugin;
+use deployment::DeploymentPlugin;
 use starknet_call::StarknetPlugin;
 use tokio::TokioPlugin;
 use torii::ToriiPlugin;
+use transactions::TransactionsPlugin;
+
+use crate::GameState;
+
+/// Why the game cannot talk to the chain, shown to the player.
+#[derive(Resource, Debug, Clone)]
+pub struct NetworkFailure(pub String);
 
+/// Talks to the chain and Torii with the network settings of the
+/// [`ProfileSettings`] resource. Nothing connects until online play starts;
+/// when the settings cannot be resolved the game stays offline and reports
+/// a [`NetworkFailure`].
 pub struct NetworkingPlugin;
 impl Plugin for NetworkingPlugin {
     fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            resolve_network_config.run_if(not(resource_exists::<NetworkConfig>)),
+        );
+        app.add_plugins(AccountsPlugin);
+        app.add_plugins(ControllerPlugin);
+        app.add_plugins(DeploymentPlugin);
         app.add_plugins(StarknetPlugin);
+        app.add_plugins(TransactionsPlugin);
         app.add_plugins(ToriiPlugin);
         app.add_plugins(TokioPlugin);
     }
 }
+
+/// Inserts the [`NetworkConfig`] that the accounts, the command thread and
+/// Torii start from.
+fn resolve_network_config(mut commands: Commands, settings: Res<ProfileSettings>) {
+    info!("using network profile '{}'", settings.profile);
+    match settings.network() {
+        Ok(config) => {
+            commands.insert_resource(config);
+            commands.remove_resource::<NetworkFailure>();
+        }
+        Err(e) => {
+            error!("invalid network configuration: {e}");
+            commands.insert_resource(NetworkFailure(e.to_string()));
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use starknet::core::types::{Call, Felt};
+
+use super::accounts::AccountConfig;
+use super::starknet_call::{SendError, Signer};
+use super::transactions::{TxId, TxKind};
+
+pub const MAX_ATTEMPTS: u32 = 4;
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Identifies a transaction by who sends it and what it does, so the same
+/// move sent twice by one account is only executed once.
+pub type DedupKey = (Felt, TxKind, Vec<Felt>);
+
+pub enum Outbound {
+    SetAccount(AccountConfig),
+    Tx(OutboundTx),
+}
+
+pub struct OutboundTx {
+    pub id: TxId,
+    pub kind: TxKind,
+    pub calls: Vec<Call>,
+    pub by: Signer,
+    /// Address of the account that signs it, set when it is queued.
+    pub signer: Felt,
+}
+impl OutboundTx {
+    pub fn new(id: TxId, kind: TxKind, calls: Vec<Call>) -> OutboundTx {
+        OutboundTx {
+            id,
+            kind,
+            calls,
+            by: Signer::Player,
+            signer: Felt::ZERO,
+        }
+    }
+
+    pub fn key(&self) -> DedupKey {
+        let felts = self
+            .calls
+            .iter()
+            .flat_map(|call| {
+                [call.to, call.selector]
+                    .into_iter()
+                    .chain(call.calldata.clone())
+            })
+            .collect();
+        (self.signer, self.kind, felts)
+    }
+}
+
+/// Commands waiting for the command thread, in the order they were sent.
+/// Transactions that are queued or submitted but not final yet are tracked
+/// so duplicates can be dropped.
+pub struct OutboundQueue {
+    queue: VecDeque<Outbound>,
+    in_flight: Arc<Mutex<HashSet<DedupKey>>>,
+    /// The account player transactions queued now are sent from: the last
+    /// one switched to.
+    signer: Felt,
+    /// The account AI transactions are sent from.
+    ai_signer: Felt,
+}
+impl OutboundQueue {
+    /// An empty queue for the player account at `signer` and the AI account
+    /// at `ai_signer`.
+    pub fn new(signer: Felt, ai_signer: Felt) -> OutboundQueue {
+        OutboundQueue {
+            queue: VecDeque::new(),
+            in_flight: Arc::default(),
+            signer,
+            ai_signer,
+        }
+    }
+
+    /// Queues `item`, handing a transaction back if an identical one from
+    /// the same account is already queued or in flight.
+    pub fn push(&mut self, item: Outbound) -> Result<(), OutboundTx> {
+        let item = match item {
+            Outbound::SetAccount(account) => {
+                self.signer = account.address;
+                Outbound::SetAccount(account)
+            }
+            Outbound::Tx(mut tx) => {
+                tx.signer = match tx.by {
+                    Signer::Player => self.signer,
+                    Signer::Ai => self.ai_signer,
+                };
+                if self.is_duplicate(&tx.key()) {
+                    return Err(tx);
+                }
+                Outbound::Tx(tx)
+            }
+        };
+        self.queue.push_back(item);
+        Ok(())
+    }
+
+    fn is_duplicate(&self, key: &DedupKey) -> bool {
+        let queued = self.queue.iter().any(|queued| match queued {
+            Outbound::Tx(queued) => &queued.key() == key,
+            Outbound::SetAccount(_) => false,
+        });
+        queued || self.in_flight.lock().unwrap().contains(key)
+    }
+
+    pub fn pop(&mut self) -> Option<Outbound> {
+        self.queue.pop_front()
+    }
+
+    /// Takes the player transactions queued up to the next account switch.
+    /// Called when switching to the account they were queued for failed, so
+    /// they are not sent by another one. AI transactions stay queued.
+    pub fn take_until_switch(&mut self) -> Vec<OutboundTx> {
+        let mut taken = Vec::new();
+        let mut switched = false;
+        for item in std::mem::take(&mut self.queue) {
+            match item {
+                Outbound::Tx(tx) if !switched && tx.by == Signer::Player => taken.push(tx),
+                Outbound::SetAccount(account) => {
+                    switched = true;
+                    self.queue.push_back(Outbound::SetAccount(account));
+                }
+                item => self.queue.push_back(item),
+            }
+        }
+        taken
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn in_flight(&self) -> Arc<Mutex<HashSet<DedupKey>>> {
+        self.in_flight.clone()
+    }
+}
+
+/// Exponential backoff before retry number `attempt`, starting at 1.
+pub fn backoff(attempt: u32) -> Duration {
+    BASE_BACKOFF * 2u32.pow(attempt.saturating_sub(1))
+}
+
+/// What to do after attempt number `attempt` of a transaction failed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Retry {
+    /// The node rejected the nonce: fetch it again and resend right away.
+    ResyncNonce,
+    /// The node itself failed: resend after waiting.
+    After(Duration),
+    GiveUp,
+}
+impl Retry {
+    pub fn after(attempt: u32, error: &SendError) -> Retry {
+        if attempt >= MAX_ATTEMPTS {
+            Retry::GiveUp
+        } else if error.is_invalid_nonce() {
+            Retry::ResyncNonce
+        } else if error.is_transient() {
+            Retry::After(backoff(attempt))
+        } else {
+            Retry::GiveUp
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use starknet::core::types::StarknetError;
+    use starknet::providers::ProviderError;
+
+    use super::*;
+
+    fn account(address: u64) -> AccountConfig {
+        AccountConfig {
+            name: format!("account_{address}"),
+            address: Felt::from(address),
+            keystore: PathBuf::new(),
+            password_env: None,
+            controller: None,
+        }
+    }
+
+    fn claim(id: u64, x: u8) -> Outbound {
+        let call = Call {
+            to: Felt::from(0xac7u64),
+            selector: Felt::from(0x5e1u64),
+            calldata: vec![Felt::ONE, Felt::from(x), Felt::ZERO],
+        };
+        Outbound::Tx(OutboundTx::new(TxId(id), TxKind::ClaimTile, vec![call]))
+    }
+
+    fn ai_claim(id: u64, x: u8) -> Outbound {
+        match claim(id, x) {
+            Outbound::Tx(tx) => Outbound::Tx(OutboundTx {
+                by: Signer::Ai,
+                ..tx
+            }),
+            other => other,
+        }
+    }
+
+    fn rejected(error: StarknetError) -> SendError {
+        SendError::Provider(ProviderError::StarknetError(error))
+    }
+
+    #[test]
+    fn drops_the_same_call_from_the_same_account() {
+        let mut queue = OutboundQueue::new(Felt::ONE, Felt::from(9u64));
+
+        assert!(queue.push(claim(0, 2)).is_ok());
+        assert!(queue.push(claim(1, 3)).is_ok());
+        let duplicate = queue.push(claim(2, 2)).unwrap_err();
+
+        assert_eq!(duplicate.id, TxId(2));
+        assert_eq!(duplicate.signer, Felt::ONE);
+    }
+
+    #[test]
+    fn keeps_the_same_call_from_another_account() {
+        let mut queue = OutboundQueue::new(Felt::ONE, Felt::from(9u64));
+
+        assert!(queue.push(claim(0, 2)).is_ok());
+        assert!(queue.push(Outbound::SetAccount(account(2))).is_ok());
+        assert!(queue.push(claim(1, 2)).is_ok());
+        assert!(queue.push(Outbound::SetAccount(account(1))).is_ok());
+        assert!(queue.push(claim(2, 2)).is_err());
+    }
+
+    #[test]
+    fn keeps_the_same_call_from_the_ai() {
+        let mut queue = OutboundQueue::new(Felt::ONE, Felt::from(9u64));
+
+        assert!(queue.push(claim(0, 2)).is_ok());
+        assert!(queue.push(ai_claim(1, 2)).is_ok());
+        let duplicate = queue.push(ai_claim(2, 2)).unwrap_err();
+
+        assert_eq!(duplicate.signer, Felt::from(9u64));
+    }
+
+    #[test]
+    fn drops_calls_still_in_flight() {
+        let mut queue = OutboundQueue::new(Felt::ONE, Felt::from(9u64));
+        queue.push(claim(0, 2)).unwrap();
+        let Some(Outbound::Tx(sent)) = queue.pop() else {
+            panic!("expected the queued transaction");
+        };
+        queue.in_flight().lock().unwrap().insert(sent.key());
+
+        assert!(queue.push(claim(1, 2)).is_err());
+
+        queue.in_flight().lock().unwrap().remove(&sent.key());
+        assert!(queue.push(claim(2, 2)).is_ok());
+    }
+
+    #[test]
+    fn takes_the_calls_of_a_failed_switch() {
+        let mut queue = OutboundQueue::new(Felt::ONE, Felt::from(9u64));
+        queue.push(claim(0, 1)).unwrap();
+        queue.push(Outbound::SetAccount(account(2))).unwrap();
+        queue.push(claim(1, 2)).unwrap();
+        queue.push(claim(2, 3)).unwrap();
+        queue.push(Outbound::SetAccount(account(3))).unwrap();
+        queue.push(claim(3, 4)).unwrap();
+
+        assert!(matches!(queue.pop(), Some(Outbound::Tx(tx)) if tx.id == TxId(0)));
+        assert!(matches!(queue.pop(), Some(Outbound::SetAccount(a)) if a.address == Felt::TWO));
+        let stranded = queue.take_until_switch();
+
+        let ids: Vec<_> = stranded.iter().map(|tx| (tx.id, tx.signer)).collect();
+        assert_eq!(ids, [(TxId(1), Felt::TWO), (TxId(2), Felt::TWO)]);
+        assert!(matches!(queue.pop(), Some(Outbound::SetAccount(a)) if a.address == Felt::THREE));
+        assert!(matches!(queue.pop(), Some(Outbound::Tx(tx)) if tx.id == TxId(3)));
+        assert!(queue.take_until_switch().is_empty());
+    }
+
+    #[test]
+    fn keeps_the_ai_calls_of_a_failed_switch() {
+        let mut queue = OutboundQueue::new(Felt::ONE, Felt::from(9u64));
+        queue.push(Outbound::SetAccount(account(2))).unwrap();
+        queue.push(claim(0, 1)).unwrap();
+        queue.push(ai_claim(1, 2)).unwrap();
+        queue.push(claim(2, 3)).unwrap();
+
+        assert!(matches!(queue.pop(), Some(Outbound::SetAccount(_))));
+        let stranded = queue.take_until_switch();
+
+        let ids: Vec<_> = stranded.iter().map(|tx| tx.id).collect();
+        assert_eq!(ids, [TxId(0), TxId(2)]);
+        assert!(matches!(queue.pop(), Some(Outbound::Tx(tx)) if tx.id == TxId(1)));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(2), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn retries_transient_failures_with_backoff() {
+        let error = SendError::Provider(ProviderError::RateLimited);
+
+        assert_eq!(Retry::after(1, &error), Retry::After(backoff(1)));
+        assert_eq!(Retry::after(2, &error), Retry::After(backoff(2)));
+        assert_eq!(Retry::after(MAX_ATTEMPTS, &error), Retry::GiveUp);
+    }
+
+    #[test]
+    fn resyncs_the_nonce_when_the_node_rejects_it() {
+        let invalid = rejected(StarknetError::InvalidTransactionNonce);
+        let validation = rejected(StarknetError::ValidationFailure(
+            "Invalid transaction nonce of contract at address 0x1".to_string(),
+        ));
+
+        assert_eq!(Retry::after(1, &invalid), Retry::ResyncNonce);
+        assert_eq!(Retry::after(1, &validation), Retry::ResyncNonce);
+        assert_eq!(Retry::after(MAX_ATTEMPTS, &invalid), Retry::GiveUp);
+    }
+
+    #[test]
+    fn gives_up_on_other_failures() {
+        let error = SendError::Account("signature rejected".to_string());
+
+        assert_eq!(Retry::after(1, &error), Retry::GiveUp);
+    }
+}
@@ -1,58 +1,128 @@
+use account_sdk::account::session::account::SessionAccount;
 use bevy::prelude::*;
 use starknet::{
-    accounts::{Account, SingleOwnerAccount},
+    accounts::{Account, AccountError, ConnectedAccount, SingleOwnerAccount},
     core::{
-        types::{Call, Felt},
+        types::{Call, Felt, InvokeTransactionResult, StarknetError},
         utils::get_selector_from_name,
     },
-    providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider, Url},
+    providers::{jsonrpc::HttpTransport, JsonRpcClient, ProviderError, Url},
     signers::{LocalWallet, SigningKey},
 };
-use tokio::sync::mpsc;
-
-use super::config::{
-    GAME_ACTIONS_CONTRACT_ADDRESS, PLAYER_ONE_ADDRESS, PLAYER_ONE_PRIVATE_KEY, PLAYER_TWO_ADDRESS,
-    PLAYER_TWO_PRIVATE_KEY, RPC_URL,
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
-use super::tokio::{TokioRuntimeResource, TokioRuntimeState};
+use tokio::sync::{mpsc, oneshot};
+
+pub use crate::rules::GridPos;
+
+use super::accounts::{AccountConfig, SelectedAccount};
+use super::config::NetworkConfig;
+use super::controller::{start_controller, SessionPolicies};
+use super::deployment::VerifiedChain;
+use super::queue::{Outbound, OutboundQueue, OutboundTx, Retry};
+use super::tokio::TokioRuntimeResource;
+use super::transactions::{TxFee, TxId, TxKind, TxStatus, TxTracker, TxUpdateChannel};
+use super::NetworkFailure;
 
 pub struct StarknetPlugin;
 impl Plugin for StarknetPlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<StarknetServerState>();
-        app.insert_resource(ClaimTilePosition {
-            x: "10",
-            y: "10",
-            current_selection: false,
-        });
         app.add_systems(
-            OnEnter(TokioRuntimeState::Ready),
-            spawn_starknet_caller_thread,
+            Update,
+            spawn_starknet_caller_thread.run_if(resource_added::<VerifiedChain>),
+        );
+        app.add_systems(
+            Update,
+            await_starknet_startup.run_if(resource_exists::<StarknetStartup>),
         );
         app.add_systems(
             Update,
-            handle_player_inputs.run_if(in_state(StarknetServerState::Ready)),
+            switch_account
+                .run_if(in_state(StarknetServerState::Ready))
+                .run_if(resource_changed::<SelectedAccount>),
         );
     }
 }
 
 #[derive(Resource)]
 pub struct StarknetChannel {
-    tx: mpsc::Sender<StarknetCommands>,
+    tx: mpsc::Sender<(TxId, Signer, StarknetCommands)>,
+    next_id: AtomicU64,
+}
+impl StarknetChannel {
+    /// Queues `command` for the command thread. The returned id identifies
+    /// the command in `TxStatusChanged` events.
+    pub fn send(&self, command: StarknetCommands) -> Option<TxId> {
+        self.send_as(Signer::Player, command)
+    }
+
+    /// Queues `command` to be signed by `signer`.
+    pub fn send_as(&self, signer: Signer, command: StarknetCommands) -> Option<TxId> {
+        let id = TxId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.tx.try_send((id, signer, command)).ok().map(|_| id)
+    }
+}
+
+/// Which account signs a transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Signer {
+    /// The selected account, switched with `SetAccount`.
+    #[default]
+    Player,
+    /// The `ai_account`, which keeps its own connection and nonce.
+    Ai,
 }
 
 pub enum StarknetCommands {
-    SetAccountPlayerOne,
-    SetAccountPlayerTwo,
+    SetAccount(AccountConfig),
     SendCreateGameTx,
-    SendJoinGameTx,
-    SendClaimTileTx(&'static str, &'static str),
+    SendJoinGameTx { game_id: u128 },
+    SendClaimTileTx { game_id: u128, pos: GridPos },
+}
+impl StarknetCommands {
+    /// The kind, `actions` entrypoint and calldata of a transaction command.
+    fn entrypoint(&self) -> Option<(TxKind, &'static str, Vec<Felt>)> {
+        match self {
+            StarknetCommands::SetAccount(_) => None,
+            StarknetCommands::SendCreateGameTx => Some((TxKind::CreateGame, "create_game", vec![])),
+            StarknetCommands::SendJoinGameTx { game_id } => {
+                Some((TxKind::JoinGame, "join_game", vec![Felt::from(*game_id)]))
+            }
+            StarknetCommands::SendClaimTileTx { game_id, pos } => Some((
+                TxKind::ClaimTile,
+                "claim_tile",
+                vec![Felt::from(*game_id), Felt::from(pos.x), Felt::from(pos.y)],
+            )),
+        }
+    }
+
+    fn calls(&self, actions_address: Felt) -> Option<(TxKind, Vec<Call>)> {
+        let (kind, entrypoint, calldata) = self.entrypoint()?;
+        let call = Call {
+            to: actions_address,
+            selector: get_selector_from_name(entrypoint).unwrap(),
+            calldata,
+        };
+        Some((kind, vec![call]))
+    }
+}
+
+/// The game that join and claim commands are sent for.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveGame {
+    pub game_id: u128,
 }
 
 #[derive(Resource)]
 pub struct ClaimTilePosition {
-    pub x: &'static str,
-    pub y: &'static str,
+    pub pos: GridPos,
     pub current_selection: bool,
 }
 
@@ -61,177 +131,378 @@ enum StarknetServerState {
     #[default]
     NotReady,
     Ready,
+    /// The account could not be connected, see [`NetworkFailure`].
+    Failed,
 }
 
-fn handle_player_inputs(
-    channel: Res<StarknetChannel>,
-    input: Res<ButtonInput<KeyCode>>,
-    mut claim_resource: ResMut<ClaimTilePosition>,
-) {
-    let keys = input.get_just_pressed();
-    let mut modify_inputs = false;
-    let mut value = "10";
-
-    for key in keys {
-        match key {
-            KeyCode::Digit0 => {
-                modify_inputs = true;
-                value = "0";
-            }
-            KeyCode::Digit1 => {
-                modify_inputs = true;
-                value = "1";
-            }
-            KeyCode::Digit2 => {
-                modify_inputs = true;
-                value = "2";
-            }
-            KeyCode::Digit3 => {
-                modify_inputs = true;
-                value = "3";
-            }
-            KeyCode::Digit4 => {
-                modify_inputs = true;
-                value = "4";
-            }
-            KeyCode::Digit5 => {
-                modify_inputs = true;
-                value = "5";
-            }
-            KeyCode::Digit6 => {
-                modify_inputs = true;
-                value = "6";
-            }
-            KeyCode::Digit7 => {
-                modify_inputs = true;
-                value = "7";
-            }
-            KeyCode::KeyX => {
-                claim_resource.current_selection = false;
-            }
-            KeyCode::KeyY => {
-                claim_resource.current_selection = true;
-            }
-            KeyCode::KeyQ => {
-                let _ = channel.tx.try_send(StarknetCommands::SetAccountPlayerOne);
-            }
-            KeyCode::KeyW => {
-                let _ = channel.tx.try_send(StarknetCommands::SetAccountPlayerTwo);
-            }
-            KeyCode::KeyN => {
-                let _ = channel.tx.try_send(StarknetCommands::SendCreateGameTx);
-            }
-            KeyCode::KeyJ => {
-                let _ = channel.tx.try_send(StarknetCommands::SendJoinGameTx);
-            }
-            KeyCode::Space => {
-                let _ = channel.tx.try_send(StarknetCommands::SendClaimTileTx(
-                    claim_resource.x,
-                    claim_resource.y,
-                ));
-            }
-            _ => {}
-        }
+/// The command thread while it starts up.
+#[derive(Resource)]
+struct StarknetStartup {
+    started: oneshot::Receiver<Result<(), String>>,
+    channel: Option<StarknetChannel>,
+}
 
-        if modify_inputs {
-            if claim_resource.current_selection {
-                claim_resource.y = value;
-                info!("changed y selection to {value}");
-            } else {
-                claim_resource.x = value;
-                info!("changed x selection to {value}");
-            }
-        }
-    }
+fn switch_account(channel: Res<StarknetChannel>, selected: Res<SelectedAccount>) {
+    let _ = channel.send(StarknetCommands::SetAccount(selected.0.clone()));
 }
 
 fn spawn_starknet_caller_thread(
     mut commands: Commands,
     rt: Res<TokioRuntimeResource>,
-    mut next_state: ResMut<NextState<StarknetServerState>>,
+    config: Res<NetworkConfig>,
+    chain: Res<VerifiedChain>,
+    policies: Res<SessionPolicies>,
+    selected: Option<Res<SelectedAccount>>,
 ) {
-    let (tx, mut rx) = mpsc::channel::<StarknetCommands>(64);
+    let (tx, mut rx) = mpsc::channel::<(TxId, Signer, StarknetCommands)>(64);
+    let (updates_tx, updates_rx) = mpsc::channel(64);
+    let (started_tx, started_rx) = oneshot::channel();
+    let config = config.clone();
+    let policies = policies.clone();
+    let chain_id = chain.chain_id;
+    let Some(selected) = selected.map(|s| s.0.clone()) else {
+        info!("no account configured, not sending transactions");
+        return;
+    };
+    let ai_config = config
+        .ai_account
+        .as_ref()
+        .and_then(|name| config.accounts.iter().find(|a| &a.name == name))
+        .cloned();
 
     let _ = rt.0.spawn(async move {
-        let provider = get_rpc_provider().await;
-        let (signer, address) = get_player1_account();
-        let chain_id = provider.chain_id().await.unwrap();
-
-        let mut account = create_player_account(provider, signer, address, chain_id);
+        let provider = Arc::new(get_rpc_provider(&config.rpc_url));
+        let mut keys = HashMap::new();
+        let Some(connected) =
+            connect_account(&config, &policies, &mut keys, &selected, chain_id).await
+        else {
+            let _ = started_tx.send(Err(format!("cannot connect account '{}'", selected.name)));
+            return;
+        };
+        let _ = started_tx.send(Ok(()));
+        let actions_address = config.actions_address;
+        let fee_multiplier = config.fee_multiplier;
+        let mut queue = OutboundQueue::new(
+            connected.address(),
+            ai_config.as_ref().map_or(Felt::ZERO, |ai| ai.address),
+        );
+        // `None` after a failed switch, until the next one succeeds.
+        let mut account = Some(connected);
+        let tracker = TxTracker {
+            provider,
+            updates: updates_tx,
+            in_flight: queue.in_flight(),
+        };
+        // Next nonce of the connected account, fetched again after a switch
+        // or when the node rejects it.
+        let mut nonce = None;
+        // The AI account, connected when it first sends, with its own nonce.
+        let mut ai = None;
+        let mut ai_nonce = None;
 
         info!("Started STARKNET TX SENDING SERVER...");
-        while let Some(starknet_command) = rx.recv().await {
-            match starknet_command {
-                StarknetCommands::SetAccountPlayerOne => {
-                    let provider = get_rpc_provider().await;
-                    let (signer, address) = get_player1_account();
-                    let chain_id = provider.chain_id().await.unwrap();
-
-                    account = create_player_account(provider, signer, address, chain_id);
-                    info!("Switched to player one.");
-                }
-                StarknetCommands::SetAccountPlayerTwo => {
-                    let provider = get_rpc_provider().await;
-                    let (signer, address) = get_player2_account();
-                    let chain_id = provider.chain_id().await.unwrap();
+        loop {
+            if queue.is_empty() {
+                let Some(command) = rx.recv().await else {
+                    break;
+                };
+                enqueue(&mut queue, &tracker, actions_address, command).await;
+            }
+            while let Ok(command) = rx.try_recv() {
+                enqueue(&mut queue, &tracker, actions_address, command).await;
+            }
 
-                    account = create_player_account(provider, signer, address, chain_id);
-                    info!("Switched to player two.");
-                }
-                StarknetCommands::SendCreateGameTx => {
-                    let res = send_create_game_tx(&account).await;
-                    info!("Sending a create_game transaction.");
-                    match res {
-                        Ok(tx) => info!("{tx:?}"),
-                        Err(e) => info!("{e:?}"),
+            match queue.pop() {
+                Some(Outbound::SetAccount(selected)) => {
+                    if account
+                        .as_ref()
+                        .is_some_and(|account| account.address() == selected.address)
+                    {
+                        continue;
                     }
-                }
-                StarknetCommands::SendJoinGameTx => {
-                    let res = send_join_game_tx(&account).await;
-                    match res {
-                        Ok(tx) => info!("{tx:?}"),
-                        Err(e) => info!("{e:?}"),
+                    account =
+                        connect_account(&config, &policies, &mut keys, &selected, chain_id).await;
+                    nonce = None;
+                    if account.is_some() {
+                        info!("Switched to account '{}'.", selected.name);
+                        continue;
+                    }
+
+                    let error =
+                        SendError::Account(format!("cannot switch to account '{}'", selected.name));
+                    error!("{error}");
+                    for tx in queue.take_until_switch() {
+                        tracker.failed(tx.id, tx.kind, &error, false).await;
                     }
                 }
-                StarknetCommands::SendClaimTileTx(x, y) => {
-                    let res = send_claim_tile_tx(&account, x, y).await;
-                    match res {
-                        Ok(tx) => info!("{tx:?}"),
-                        Err(e) => info!("{e:?}"),
+                Some(Outbound::Tx(tx)) if tx.by == Signer::Ai => {
+                    if ai.is_none() {
+                        if let Some(ai_config) = ai_config.as_ref() {
+                            ai =
+                                connect_account(&config, &policies, &mut keys, ai_config, chain_id)
+                                    .await;
+                            ai_nonce = None;
+                        }
+                    }
+                    match ai.as_ref() {
+                        Some(ai) => submit(ai, &tracker, &mut ai_nonce, tx, fee_multiplier).await,
+                        None => {
+                            let error = SendError::Account("no AI account connected".to_string());
+                            tracker.failed(tx.id, tx.kind, &error, false).await;
+                        }
                     }
                 }
+                Some(Outbound::Tx(tx)) => match account.as_ref() {
+                    Some(account) => {
+                        submit(account, &tracker, &mut nonce, tx, fee_multiplier).await;
+                    }
+                    None => {
+                        let error = SendError::Account("no account connected".to_string());
+                        tracker.failed(tx.id, tx.kind, &error, false).await;
+                    }
+                },
+                None => {}
             }
         }
     });
 
-    commands.insert_resource(StarknetChannel { tx });
-    next_state.set(StarknetServerState::Ready);
+    commands.insert_resource(StarknetStartup {
+        started: started_rx,
+        channel: Some(StarknetChannel {
+            tx,
+            next_id: AtomicU64::new(0),
+        }),
+    });
+    commands.insert_resource(TxUpdateChannel { rx: updates_rx });
+}
+
+/// Hands out the command channel once the command thread connected the
+/// account, or reports why it could not.
+fn await_starknet_startup(
+    mut commands: Commands,
+    mut startup: ResMut<StarknetStartup>,
+    mut next_state: ResMut<NextState<StarknetServerState>>,
+) {
+    let started = match startup.started.try_recv() {
+        Ok(started) => started,
+        Err(oneshot::error::TryRecvError::Empty) => return,
+        Err(oneshot::error::TryRecvError::Closed) => {
+            Err("the command thread stopped while starting".to_string())
+        }
+    };
+    commands.remove_resource::<StarknetStartup>();
+
+    match started {
+        Ok(()) => {
+            if let Some(channel) = startup.channel.take() {
+                commands.insert_resource(channel);
+            }
+            next_state.set(StarknetServerState::Ready);
+        }
+        Err(reason) => {
+            error!("cannot start sending transactions: {reason}");
+            commands.insert_resource(NetworkFailure(reason));
+            next_state.set(StarknetServerState::Failed);
+        }
+    }
 }
 
-async fn get_rpc_provider() -> JsonRpcClient<HttpTransport> {
-    let provider = JsonRpcClient::new(HttpTransport::new(Url::parse(RPC_URL).unwrap()));
+fn get_rpc_provider(rpc_url: &Url) -> JsonRpcClient<HttpTransport> {
+    let provider = JsonRpcClient::new(HttpTransport::new(rpc_url.clone()));
 
     provider
 }
 
-fn get_player1_account() -> (LocalWallet, Felt) {
-    let signer = LocalWallet::from(SigningKey::from_secret_scalar(
-        Felt::from_hex(PLAYER_ONE_PRIVATE_KEY).unwrap(),
-    ));
+/// The signer used by the command thread, either a plain account or a
+/// Cartridge Controller session.
+pub enum GameAccount {
+    SingleOwner(SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>),
+    Controller(SessionAccount),
+}
+impl GameAccount {
+    pub fn address(&self) -> Felt {
+        match self {
+            GameAccount::SingleOwner(account) => account.address(),
+            GameAccount::Controller(account) => account.address(),
+        }
+    }
 
-    let address = Felt::from_hex(PLAYER_ONE_ADDRESS).unwrap();
+    pub async fn fetch_nonce(&self) -> Result<Felt, SendError> {
+        let nonce = match self {
+            GameAccount::SingleOwner(account) => account.get_nonce().await,
+            GameAccount::Controller(account) => account.get_nonce().await,
+        };
+        nonce.map_err(SendError::Provider)
+    }
 
-    (signer, address)
+    /// Simulates `calls` and derives the resource bounds to send them with.
+    pub async fn estimate_fee(
+        &self,
+        calls: Vec<Call>,
+        nonce: Felt,
+        fee_multiplier: f64,
+    ) -> Result<TxFee, SendError> {
+        let estimate = match self {
+            GameAccount::SingleOwner(account) => {
+                account
+                    .execute_v3(calls)
+                    .nonce(nonce)
+                    .estimate_fee()
+                    .await?
+            }
+            GameAccount::Controller(account) => {
+                account
+                    .execute_v3(calls)
+                    .nonce(nonce)
+                    .estimate_fee()
+                    .await?
+            }
+        };
+        TxFee::from_estimate(&estimate, fee_multiplier)
+            .ok_or_else(|| SendError::Account(format!("fee estimate out of range: {estimate:?}")))
+    }
+
+    pub async fn execute(
+        &self,
+        calls: Vec<Call>,
+        nonce: Felt,
+        fee: &TxFee,
+    ) -> Result<InvokeTransactionResult, SendError> {
+        match self {
+            GameAccount::SingleOwner(account) => Ok(account
+                .execute_v3(calls)
+                .nonce(nonce)
+                .gas(fee.l1_gas)
+                .gas_price(fee.l1_gas_price)
+                .send()
+                .await?),
+            GameAccount::Controller(account) => Ok(account
+                .execute_v3(calls)
+                .nonce(nonce)
+                .gas(fee.l1_gas)
+                .gas_price(fee.l1_gas_price)
+                .send()
+                .await?),
+        }
+    }
 }
 
-fn get_player2_account() -> (LocalWallet, Felt) {
-    let signer = LocalWallet::from(SigningKey::from_secret_scalar(
-        Felt::from_hex(PLAYER_TWO_PRIVATE_KEY).unwrap(),
-    ));
+#[derive(Debug)]
+pub enum SendError {
+    Provider(ProviderError),
+    Account(String),
+}
+impl SendError {
+    /// The contract's revert trace, when the failure came from executing it.
+    pub fn revert_reason(&self) -> Option<String> {
+        match self {
+            SendError::Provider(ProviderError::StarknetError(
+                StarknetError::TransactionExecutionError(data),
+            )) => Some(data.execution_error.clone()),
+            SendError::Provider(ProviderError::StarknetError(StarknetError::ContractError(
+                data,
+            ))) => Some(data.revert_error.clone()),
+            _ => None,
+        }
+    }
 
-    let address = Felt::from_hex(PLAYER_TWO_ADDRESS).unwrap();
+    /// The node rejected the nonce, so the local one is out of sync.
+    pub fn is_invalid_nonce(&self) -> bool {
+        match self {
+            SendError::Provider(ProviderError::StarknetError(
+                StarknetError::InvalidTransactionNonce,
+            )) => true,
+            SendError::Provider(ProviderError::StarknetError(
+                StarknetError::ValidationFailure(reason),
+            )) => reason.to_lowercase().contains("nonce"),
+            _ => false,
+        }
+    }
 
-    (signer, address)
+    /// Failures of the RPC node itself rather than of the transaction.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            SendError::Provider(ProviderError::RateLimited | ProviderError::Other(_))
+        )
+    }
+}
+impl<S: std::error::Error> From<AccountError<S>> for SendError {
+    fn from(value: AccountError<S>) -> Self {
+        match value {
+            AccountError::Provider(e) => SendError::Provider(e),
+            other => SendError::Account(other.to_string()),
+        }
+    }
+}
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Provider(e) => write!(f, "{e}"),
+            SendError::Account(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// Unlocks the keystore of `account` once and caches the key, so switching
+/// back and forth only prompts for each password a single time.
+async fn unlock_account(
+    keys: &mut HashMap<String, SigningKey>,
+    account: &AccountConfig,
+) -> Option<SigningKey> {
+    if let Some(key) = keys.get(&account.name) {
+        return Some(key.clone());
+    }
+
+    let to_unlock = account.clone();
+    match tokio::task::spawn_blocking(move || to_unlock.unlock()).await {
+        Ok(Ok(key)) => {
+            keys.insert(account.name.clone(), key.clone());
+            Some(key)
+        }
+        Ok(Err(e)) => {
+            error!("{e}");
+            None
+        }
+        Err(e) => {
+            error!("keystore task failed: {e}");
+            None
+        }
+    }
+}
+
+async fn connect_account(
+    config: &NetworkConfig,
+    policies: &SessionPolicies,
+    keys: &mut HashMap<String, SigningKey>,
+    selected: &AccountConfig,
+    chain_id: Felt,
+) -> Option<GameAccount> {
+    let key = unlock_account(keys, selected).await?;
+
+    let Some(controller) = selected.controller.as_ref() else {
+        let provider = get_rpc_provider(&config.rpc_url);
+        let account = create_player_account(provider, key.into(), selected.address, chain_id);
+        return Some(GameAccount::SingleOwner(account));
+    };
+
+    let session = start_controller(
+        controller,
+        key,
+        selected.address,
+        &config.rpc_url,
+        chain_id,
+        config.actions_address,
+        policies,
+    )
+    .await;
+    match session {
+        Ok(session) => Some(GameAccount::Controller(session)),
+        Err(e) => {
+            error!("controller '{}': {e}", controller.username);
+            None
+        }
+    }
 }
 
 fn create_player_account(
@@ -251,69 +522,100 @@ fn create_player_account(
     account
 }
 
-async fn send_create_game_tx(
-    account: &SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>,
-) -> Result<
-    starknet::core::types::InvokeTransactionResult,
-    starknet::accounts::AccountError<
-        starknet::accounts::single_owner::SignError<starknet::signers::local_wallet::SignError>,
-    >,
-> {
-    let tx = account
-        .execute_v3(vec![Call {
-            to: Felt::from_hex(GAME_ACTIONS_CONTRACT_ADDRESS).unwrap(),
-            selector: get_selector_from_name("create_game").unwrap(),
-            calldata: vec![],
-        }])
-        .send()
-        .await;
-
-    tx
+async fn enqueue(
+    queue: &mut OutboundQueue,
+    tracker: &TxTracker,
+    actions_address: Felt,
+    (id, signer, command): (TxId, Signer, StarknetCommands),
+) {
+    let Some((kind, calls)) = command.calls(actions_address) else {
+        if let StarknetCommands::SetAccount(selected) = command {
+            let _ = queue.push(Outbound::SetAccount(selected));
+        }
+        return;
+    };
+
+    let tx = OutboundTx {
+        by: signer,
+        ..OutboundTx::new(id, kind, calls)
+    };
+    match queue.push(Outbound::Tx(tx)) {
+        Ok(()) => tracker.report(id, kind, TxStatus::Queued).await,
+        Err(duplicate) => {
+            info!("tx {id:?} ({kind:?}) is already pending, dropping it");
+            tracker
+                .report(duplicate.id, duplicate.kind, TxStatus::Duplicate)
+                .await
+        }
+    }
 }
 
-async fn send_join_game_tx(
-    account: &SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>,
-) -> Result<
-    starknet::core::types::InvokeTransactionResult,
-    starknet::accounts::AccountError<
-        starknet::accounts::single_owner::SignError<starknet::signers::local_wallet::SignError>,
-    >,
-> {
-    let tx = account
-        .execute_v3(vec![Call {
-            to: Felt::from_hex(GAME_ACTIONS_CONTRACT_ADDRESS).unwrap(),
-            selector: get_selector_from_name("join_game").unwrap(),
-            calldata: vec![Felt::from_hex_unchecked("0x1")],
-        }])
-        .send()
-        .await;
+/// Sends `tx`, retrying transient RPC failures with backoff and resyncing
+/// `nonce` when the node rejects it.
+async fn submit(
+    account: &GameAccount,
+    tracker: &TxTracker,
+    nonce: &mut Option<Felt>,
+    tx: OutboundTx,
+    fee_multiplier: f64,
+) {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let (error, estimating) = match send(account, tracker, nonce, &tx, fee_multiplier).await {
+            Ok(sent) => {
+                tracker.submitted(tx.id, tx.kind, tx.key(), sent).await;
+                return;
+            }
+            Err(failure) => failure,
+        };
+
+        let retry = Retry::after(attempt, &error);
+        if retry == Retry::GiveUp {
+            tracker.failed(tx.id, tx.kind, &error, estimating).await;
+            return;
+        }
 
-    tx
+        warn!(
+            "tx {:?} ({:?}) attempt {attempt} failed, retrying: {error}",
+            tx.id, tx.kind
+        );
+        match retry {
+            Retry::ResyncNonce => *nonce = None,
+            Retry::After(delay) => tokio::time::sleep(delay).await,
+            Retry::GiveUp => {}
+        }
+    }
 }
 
-async fn send_claim_tile_tx(
-    account: &SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>,
-    x: &'static str,
-    y: &'static str,
-) -> Result<
-    starknet::core::types::InvokeTransactionResult,
-    starknet::accounts::AccountError<
-        starknet::accounts::single_owner::SignError<starknet::signers::local_wallet::SignError>,
-    >,
-> {
-    let calldata = vec![
-        Felt::from_hex_unchecked("0x1"),
-        Felt::from_hex_unchecked(x),
-        Felt::from_hex_unchecked(y),
-    ];
-    let tx = account
-        .execute_v3(vec![Call {
-            to: Felt::from_hex(GAME_ACTIONS_CONTRACT_ADDRESS).unwrap(),
-            selector: get_selector_from_name("claim_tile").unwrap(),
-            calldata,
-        }])
-        .send()
+/// Simulates the calls through a fee estimation first, so reverting calls
+/// are rejected locally instead of costing a transaction. Errors are paired
+/// with whether they happened during the estimation.
+async fn send(
+    account: &GameAccount,
+    tracker: &TxTracker,
+    nonce: &mut Option<Felt>,
+    tx: &OutboundTx,
+    fee_multiplier: f64,
+) -> Result<InvokeTransactionResult, (SendError, bool)> {
+    let current = match *nonce {
+        Some(current) => current,
+        None => account.fetch_nonce().await.map_err(|e| (e, false))?,
+    };
+    *nonce = Some(current);
+
+    let fee = account
+        .estimate_fee(tx.calls.clone(), current, fee_multiplier)
+        .await
+        .map_err(|e| (e, true))?;
+    tracker
+        .report(tx.id, tx.kind, TxStatus::Estimated(fee.clone()))
         .await;
 
-    tx
+    let sent = account
+        .execute(tx.calls.clone(), current, &fee)
+        .await
+        .map_err(|e| (e, false))?;
+    *nonce = Some(current + Felt::ONE);
+    Ok(sent)
 }
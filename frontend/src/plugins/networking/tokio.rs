@@ -1,13 +1,17 @@
 use bevy::prelude::*;
 use tokio::runtime::Runtime;
 
+use super::config::NetworkConfig;
+
 pub struct TokioPlugin;
 impl Plugin for TokioPlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<TokioRuntimeState>();
         app.add_systems(
             Update,
-            setup_tokio_runtime.run_if(in_state(TokioRuntimeState::NotReady)),
+            setup_tokio_runtime
+                .run_if(in_state(TokioRuntimeState::NotReady))
+                .run_if(resource_exists::<NetworkConfig>),
         );
     }
 }
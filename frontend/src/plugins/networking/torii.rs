@@ -1,42 +1,187 @@
-use super::config::{GAME_WORLD_ADDRESS, RPC_URL, TORII_RELAY_URL, TORII_URL};
-use super::tokio::{TokioRuntimeResource, TokioRuntimeState};
+use std::{collections::HashMap, fmt, time::Duration};
+
+use super::config::NetworkConfig;
+use super::deployment::VerifiedChain;
+use super::starknet_call::ActiveGame;
+use super::tokio::TokioRuntimeResource;
+use crate::plugins::dojo_models::DojoModelRegistry;
 use bevy::prelude::*;
 use bevy::tasks::futures_lite::StreamExt;
 use dojo_types::schema::Struct as DojoStruct;
+use serde::{Deserialize, Serialize};
 use starknet::core::types::Felt;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use torii_client::client::error::Error as ToriiError;
 use torii_client::client::Client as ToriiClient;
 use torii_grpc::types::schema::Entity as ToriiEntity;
-use torii_grpc::types::Query as ToriiQuery;
+use torii_grpc::types::{
+    Clause, CompositeClause, EntityKeysClause, KeysClause, LogicalOperator, PatternMatching,
+    Query as ToriiQuery,
+};
 
 pub struct ToriiPlugin;
 impl Plugin for ToriiPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_entity_channel_resource);
         app.add_systems(
-            OnEnter(TokioRuntimeState::Ready),
-            spawn_tokio_runtime_thread,
+            Update,
+            spawn_tokio_runtime_thread.run_if(resource_added::<VerifiedChain>),
         );
+        app.init_resource::<ToriiConnectionState>();
+        app.init_resource::<EntityIndex>();
         app.add_systems(Update, spawn_torii_entities);
+        app.add_systems(
+            Update,
+            receive_connection_state.run_if(resource_exists::<ToriiStateChannel>),
+        );
+        app.add_systems(
+            Update,
+            follow_active_game
+                .run_if(resource_exists::<ToriiScope>)
+                .run_if(resource_changed::<ActiveGame>),
+        );
+    }
+}
+
+const SYNC_PAGE_SIZE: u32 = 100;
+const BASE_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Where the Torii supervisor is at, for display.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub enum ToriiConnectionState {
+    /// Connection attempt number `attempt` since the last successful sync.
+    Connecting { attempt: u32 },
+    /// Existing entities were synced and updates are subscribed to.
+    Synced { entities: usize },
+    /// At least one live update arrived since the last sync.
+    Streaming,
+    Disconnected {
+        last_error: String,
+        retry_in: Duration,
+    },
+}
+impl Default for ToriiConnectionState {
+    fn default() -> Self {
+        ToriiConnectionState::Connecting { attempt: 0 }
+    }
+}
+impl fmt::Display for ToriiConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToriiConnectionState::Connecting { attempt } if *attempt > 1 => {
+                write!(f, "connecting to torii (attempt {attempt})")
+            }
+            ToriiConnectionState::Connecting { .. } => write!(f, "connecting to torii"),
+            ToriiConnectionState::Synced { entities } => {
+                write!(f, "synced {entities} entities from torii")
+            }
+            ToriiConnectionState::Streaming => write!(f, "live"),
+            ToriiConnectionState::Disconnected {
+                last_error,
+                retry_in,
+            } => write!(
+                f,
+                "disconnected from torii: {last_error} (retrying in {}s)",
+                retry_in.as_secs()
+            ),
+        }
+    }
+}
+
+/// The entities synced from Torii.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncScope {
+    /// Every entity of the world.
+    World,
+    /// The registered models, by tag, of these game ids only.
+    Games {
+        game_ids: Vec<u128>,
+        models: Vec<String>,
+    },
+}
+impl SyncScope {
+    /// The models of `registry` for `game_id`.
+    fn game(game_id: u128, registry: &DojoModelRegistry) -> SyncScope {
+        let mut models: Vec<String> = registry.tags().map(String::from).collect();
+        models.sort();
+        SyncScope::Games {
+            game_ids: vec![game_id],
+            models,
+        }
+    }
+
+    fn keys_clauses(&self) -> Vec<KeysClause> {
+        let SyncScope::Games { game_ids, models } = self else {
+            return vec![];
+        };
+        game_ids
+            .iter()
+            .map(|game_id| KeysClause {
+                keys: vec![Some(Felt::from(*game_id))],
+                pattern_matching: PatternMatching::VariableLen,
+                models: models.clone(),
+            })
+            .collect()
+    }
+
+    fn clause(&self) -> Option<Clause> {
+        if *self == SyncScope::World {
+            return None;
+        }
+        let mut clauses: Vec<Clause> = self.keys_clauses().into_iter().map(Clause::Keys).collect();
+
+        if clauses.len() == 1 {
+            clauses.pop()
+        } else {
+            Some(Clause::Composite(CompositeClause {
+                operator: LogicalOperator::Or,
+                clauses,
+            }))
+        }
     }
 }
 
+/// Sends scope changes to the running Torii session.
+#[derive(Resource)]
+pub struct ToriiScope {
+    tx: watch::Sender<SyncScope>,
+}
+
+#[derive(Resource)]
+pub struct ToriiStateChannel {
+    rx: mpsc::Receiver<ToriiConnectionState>,
+}
+
 #[derive(Resource)]
 pub struct ToriiChannel {
     rx: mpsc::Receiver<ToriiEntity>,
     tx: mpsc::Sender<ToriiEntity>,
 }
 
-#[derive(Reflect, Debug, PartialEq, Eq, Clone)]
-pub struct BevyFelt {
-    felt_string: String,
+/// A `Felt` that can live in components and be inspected.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[reflect(opaque, Debug, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BevyFelt(pub Felt);
+impl BevyFelt {
+    pub fn is_zero(&self) -> bool {
+        self.0 == Felt::ZERO
+    }
 }
 impl From<Felt> for BevyFelt {
     fn from(value: Felt) -> Self {
-        BevyFelt {
-            felt_string: value.to_string(),
-        }
+        BevyFelt(value)
+    }
+}
+impl From<BevyFelt> for Felt {
+    fn from(value: BevyFelt) -> Self {
+        value.0
+    }
+}
+impl fmt::Display for BevyFelt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
     }
 }
 
@@ -46,92 +191,290 @@ pub struct BevyfiedDojoEntity {
     pub models: Vec<DojoStruct>,
 }
 
+/// Bevy entities of the Torii entities received so far, by hashed keys.
+#[derive(Resource, Debug, Default)]
+pub struct EntityIndex(pub HashMap<BevyFelt, Entity>);
+
+/// Triggered with the hashed keys of the entities Torii reported since the
+/// last frame.
 #[derive(Event, Debug)]
-pub struct UpdatedBevyfiedDojoEntity;
+pub struct UpdatedBevyfiedDojoEntity {
+    pub keys: Vec<BevyFelt>,
+}
 
 fn spawn_torii_entities(
     mut commands: Commands,
     mut channel: ResMut<ToriiChannel>,
+    mut index: ResMut<EntityIndex>,
     mut query: Query<&mut BevyfiedDojoEntity>,
 ) {
-    if let Ok(entity_from_torii) = channel.rx.try_recv() {
-        if let Some(mut existing_entity) = query
-            .iter_mut()
-            .find(|e| e.keys.felt_string == entity_from_torii.hashed_keys.to_string())
+    // Only the latest models of an entity matter if it changed several times.
+    let mut updates = HashMap::new();
+    while let Ok(entity_from_torii) = channel.rx.try_recv() {
+        updates.insert(
+            BevyFelt::from(entity_from_torii.hashed_keys),
+            entity_from_torii.models,
+        );
+    }
+    if updates.is_empty() {
+        return;
+    }
+
+    let keys: Vec<BevyFelt> = updates.keys().copied().collect();
+    for (key, models) in updates {
+        if let Some(mut existing_entity) = index
+            .0
+            .get(&key)
+            .and_then(|entity| query.get_mut(*entity).ok())
         {
-            info!("updating existing bevyfied entity...");
-            info!("from: {existing_entity:?}");
-            existing_entity.models = entity_from_torii.models.clone();
-            info!("to: {existing_entity:?}");
-        } else {
-            let new_entity = BevyfiedDojoEntity {
-                keys: BevyFelt {
-                    felt_string: entity_from_torii.hashed_keys.to_string(),
-                },
-                models: entity_from_torii.models,
-            };
+            debug!("updating bevyfied entity {key:?}: {models:?}");
+            existing_entity.models = models;
+        } else if !models.is_empty() {
+            let new_entity = BevyfiedDojoEntity { keys: key, models };
             info!("created new bevyfied dojo entity: {new_entity:?}");
-            commands.spawn(new_entity);
+            let entity = commands.spawn(new_entity).id();
+            index.0.insert(key, entity);
         }
+    }
 
-        commands.trigger(UpdatedBevyfiedDojoEntity);
+    commands.trigger(UpdatedBevyfiedDojoEntity { keys });
+}
+
+fn receive_connection_state(
+    mut channel: ResMut<ToriiStateChannel>,
+    mut state: ResMut<ToriiConnectionState>,
+) {
+    while let Ok(next) = channel.rx.try_recv() {
+        info!("torii: {next}");
+        *state = next;
     }
 }
 
+fn follow_active_game(
+    scope: Res<ToriiScope>,
+    active_game: Res<ActiveGame>,
+    registry: Res<DojoModelRegistry>,
+) {
+    let next = SyncScope::game(active_game.game_id, &registry);
+    scope.tx.send_if_modified(|scope| {
+        if *scope == next {
+            return false;
+        }
+        info!("following game {}", active_game.game_id);
+        *scope = next;
+        true
+    });
+}
+
 fn setup_entity_channel_resource(mut commands: Commands) {
     let (tx, rx) = mpsc::channel::<ToriiEntity>(64);
     commands.insert_resource(ToriiChannel { rx, tx });
 }
 
-fn spawn_tokio_runtime_thread(rt: Res<TokioRuntimeResource>, channel: Res<ToriiChannel>) {
+fn spawn_tokio_runtime_thread(
+    mut commands: Commands,
+    rt: Res<TokioRuntimeResource>,
+    channel: Res<ToriiChannel>,
+    config: Res<NetworkConfig>,
+    active_game: Option<Res<ActiveGame>>,
+    registry: Res<DojoModelRegistry>,
+) {
     let tx = channel.tx.clone();
-    let _ = rt.0.spawn(async move {
-        let new_client = ToriiClient::new(
-            TORII_URL.to_string(),
-            TORII_RELAY_URL.to_string(),
-            Felt::from_hex_unchecked(GAME_WORLD_ADDRESS),
-        );
-        if let Ok(client) = new_client.await {
-            if let Ok(list_of_existing_entities) = sync_entities(&client).await {
-                for entity in list_of_existing_entities.iter() {
-                    info!("torii sync: {entity:?}");
-                    let _res = tx.clone().send(entity.clone()).await;
-                }
-            }
+    let (state_tx, state_rx) = mpsc::channel(16);
+    let config = config.clone();
+    let scope = match active_game {
+        Some(active_game) => SyncScope::game(active_game.game_id, &registry),
+        None => SyncScope::World,
+    };
+    let (scope_tx, scope_rx) = watch::channel(scope);
+    let _ = rt.0.spawn(supervise_torii(config, scope_rx, tx, state_tx));
+    commands.insert_resource(ToriiStateChannel { rx: state_rx });
+    commands.insert_resource(ToriiScope { tx: scope_tx });
+}
+
+/// Keeps a Torii session alive, reconnecting with exponential backoff and
+/// syncing again after every reconnection.
+async fn supervise_torii(
+    config: NetworkConfig,
+    mut scope: watch::Receiver<SyncScope>,
+    tx: mpsc::Sender<ToriiEntity>,
+    state: mpsc::Sender<ToriiConnectionState>,
+) {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let _ = state
+            .send(ToriiConnectionState::Connecting { attempt })
+            .await;
 
-            if let Ok(mut stream) = stream_entities(&client).await {
-                loop {
-                    match stream.try_next().await {
-                        Ok(item) => {
-                            if let Some((_, entity)) = item {
-                                info!("torii stream: {entity:?}");
-                                let _res = tx.clone().send(entity.clone()).await;
-                            }
-                        }
-                        Err(e) => error!("{e}"),
+        let last_error = run_torii_session(&config, &mut scope, &tx, &state, &mut attempt).await;
+        if tx.is_closed() {
+            return;
+        }
+
+        let retry_in = reconnect_delay(attempt);
+        warn!("torii: {last_error}, reconnecting in {retry_in:?}");
+        let _ = state
+            .send(ToriiConnectionState::Disconnected {
+                last_error,
+                retry_in,
+            })
+            .await;
+        tokio::time::sleep(retry_in).await;
+    }
+}
+
+/// Connects, syncs and forwards live updates until something fails. When
+/// `scope` changes, the subscription is moved over and the new scope synced.
+/// Resets `attempt` once the sync went through and returns why the session
+/// ended.
+async fn run_torii_session(
+    config: &NetworkConfig,
+    scope: &mut watch::Receiver<SyncScope>,
+    tx: &mpsc::Sender<ToriiEntity>,
+    state: &mpsc::Sender<ToriiConnectionState>,
+    attempt: &mut u32,
+) -> String {
+    let client = match ToriiClient::new(
+        config.torii_url.clone(),
+        config.torii_relay_url.clone(),
+        config.world_address,
+    )
+    .await
+    {
+        Ok(client) => client,
+        Err(e) => return format!("cannot connect to {}: {e}", config.torii_url),
+    };
+
+    // Subscribe before syncing so no update falls between the two.
+    let current = scope.borrow_and_update().clone();
+    let mut stream = match stream_entities(&client, &current).await {
+        Ok(stream) => stream,
+        Err(e) => return format!("cannot subscribe to entity updates: {e}"),
+    };
+    let entities = match forward_synced_entities(&client, &current, tx).await {
+        Ok(entities) => entities,
+        Err(e) => return e,
+    };
+    *attempt = 0;
+    let _ = state.send(ToriiConnectionState::Synced { entities }).await;
+
+    let mut subscription_id = None;
+    let mut streaming = false;
+    loop {
+        tokio::select! {
+            item = stream.try_next() => match item {
+                Ok(Some((id, entity))) => {
+                    subscription_id = Some(id);
+                    // The first message only carries the subscription id.
+                    if entity.hashed_keys == Felt::ZERO {
+                        continue;
+                    }
+                    if !streaming {
+                        streaming = true;
+                        let _ = state.send(ToriiConnectionState::Streaming).await;
+                    }
+                    info!("torii stream: {entity:?}");
+                    if tx.send(entity).await.is_err() {
+                        return "entity channel closed".into();
                     }
                 }
+                Ok(None) => return "entity stream closed".into(),
+                Err(e) => return format!("entity stream failed: {e}"),
+            },
+            changed = scope.changed() => {
+                if changed.is_err() {
+                    return "scope channel closed".into();
+                }
+                let current = scope.borrow_and_update().clone();
+                let updated = match subscription_id {
+                    Some(id) => client
+                        .update_entity_subscription(id, entity_keys_clauses(&current))
+                        .await,
+                    None => stream_entities(&client, &current).await.map(|new_stream| {
+                        stream = new_stream;
+                    }),
+                };
+                if let Err(e) = updated {
+                    return format!("cannot update entity subscription: {e}");
+                }
+                let entities = match forward_synced_entities(&client, &current, tx).await {
+                    Ok(entities) => entities,
+                    Err(e) => return e,
+                };
+                streaming = false;
+                let _ = state.send(ToriiConnectionState::Synced { entities }).await;
             }
         }
-    });
+    }
 }
 
-async fn sync_entities(client: &ToriiClient) -> Result<Vec<ToriiEntity>, ToriiError> {
-    let query = ToriiQuery {
-        clause: None,
-        limit: 100,
-        offset: 0,
-        dont_include_hashed_keys: false,
-        order_by: vec![],
-        entity_models: vec![],
-        entity_updated_after: 0,
-    };
+/// Syncs `scope` and sends the entities to the game, returning how many
+/// there were.
+async fn forward_synced_entities(
+    client: &ToriiClient,
+    scope: &SyncScope,
+    tx: &mpsc::Sender<ToriiEntity>,
+) -> Result<usize, String> {
+    let existing_entities = sync_entities(client, scope)
+        .await
+        .map_err(|e| format!("cannot sync entities: {e}"))?;
 
-    client.entities(query).await
+    let entities = existing_entities.len();
+    for entity in existing_entities {
+        info!("torii sync: {entity:?}");
+        tx.send(entity)
+            .await
+            .map_err(|_| "entity channel closed".to_string())?;
+    }
+    Ok(entities)
+}
+
+fn reconnect_delay(attempt: u32) -> Duration {
+    BASE_RECONNECT_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_RECONNECT_DELAY)
+}
+
+/// Fetches every entity in `scope`, one page at a time.
+async fn sync_entities(
+    client: &ToriiClient,
+    scope: &SyncScope,
+) -> Result<Vec<ToriiEntity>, ToriiError> {
+    let clause = scope.clause();
+    let mut entities = vec![];
+    loop {
+        let query = ToriiQuery {
+            clause: clause.clone(),
+            limit: SYNC_PAGE_SIZE,
+            offset: entities.len() as u32,
+            dont_include_hashed_keys: false,
+            order_by: vec![],
+            entity_models: vec![],
+            entity_updated_after: 0,
+        };
+
+        let page = client.entities(query).await?;
+        let is_last = page.len() < SYNC_PAGE_SIZE as usize;
+        entities.extend(page);
+        if is_last {
+            return Ok(entities);
+        }
+    }
 }
 
 async fn stream_entities(
     client: &ToriiClient,
+    scope: &SyncScope,
 ) -> Result<torii_grpc::client::EntityUpdateStreaming, ToriiError> {
-    client.on_entity_updated(vec![]).await
+    client.on_entity_updated(entity_keys_clauses(scope)).await
+}
+
+/// Subscription clauses for `scope`; no clause subscribes to the world.
+fn entity_keys_clauses(scope: &SyncScope) -> Vec<EntityKeysClause> {
+    scope
+        .keys_clauses()
+        .into_iter()
+        .map(EntityKeysClause::Keys)
+        .collect()
 }
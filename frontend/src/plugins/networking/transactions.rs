@@ -0,0 +1,301 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use starknet::{
+    core::types::{
+        ExecutionResult, FeeEstimate, Felt, InvokeTransactionResult, StarknetError,
+        TransactionExecutionStatus, TransactionStatus,
+    },
+    providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider, ProviderError},
+};
+use tokio::sync::mpsc;
+
+use super::errors::GameError;
+use super::queue::DedupKey;
+use super::starknet_call::SendError;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_POLLS: u32 = 180;
+
+pub struct TransactionsPlugin;
+impl Plugin for TransactionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TxStatusChanged>();
+        app.add_event::<GameErrorEvent>();
+        app.init_resource::<PendingTransactions>();
+        app.add_systems(
+            Update,
+            receive_tx_updates.run_if(resource_exists::<TxUpdateChannel>),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TxId(pub u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TxKind {
+    CreateGame,
+    JoinGame,
+    ClaimTile,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Picked up by the command thread, not sent yet.
+    Queued,
+    /// Simulated successfully, about to be sent with these bounds.
+    Estimated(TxFee),
+    /// Reverted during simulation and was never sent.
+    SimulationFailed(String),
+    /// Accepted by the RPC node, not yet in a block.
+    Received(Felt),
+    AcceptedOnL2(Felt),
+    Succeeded(Felt),
+    Reverted {
+        hash: Felt,
+        reason: String,
+    },
+    /// Dropped by the sequencer before execution.
+    Rejected(Felt),
+    /// Never made it to the chain, or could not be tracked.
+    Failed(String),
+    /// Dropped because the same transaction is already queued or pending.
+    Duplicate,
+}
+impl TxStatus {
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TxStatus::Succeeded(_)
+                | TxStatus::Reverted { .. }
+                | TxStatus::Rejected(_)
+                | TxStatus::SimulationFailed(_)
+                | TxStatus::Failed(_)
+                | TxStatus::Duplicate
+        )
+    }
+}
+
+/// Estimated cost of a transaction and the resource bounds it is sent with.
+/// The RPC version in use prices everything in L1 gas, so there is no L2
+/// bound to carry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxFee {
+    pub overall_fee: u128,
+    /// Max amount and max price per unit of L1 gas.
+    pub l1_gas: u64,
+    pub l1_gas_price: u128,
+}
+impl TxFee {
+    /// Mirrors how `execute_v3` turns an estimate into resource bounds, with
+    /// `multiplier` applied to both the gas amount and the gas price.
+    pub fn from_estimate(estimate: &FeeEstimate, multiplier: f64) -> Option<TxFee> {
+        let overall_fee = u128::try_from(estimate.overall_fee).ok()?;
+        let gas_price = u128::try_from(estimate.gas_price).ok()?;
+        if gas_price == 0 {
+            return None;
+        }
+        let gas = overall_fee.div_ceil(gas_price);
+
+        Some(TxFee {
+            overall_fee,
+            l1_gas: u64::try_from((gas as f64 * multiplier) as u128).ok()?,
+            l1_gas_price: (gas_price as f64 * multiplier) as u128,
+        })
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct TxStatusChanged {
+    pub id: TxId,
+    pub kind: TxKind,
+    pub status: TxStatus,
+}
+
+/// A transaction failed on one of the contracts' game rules.
+#[derive(Event, Debug, Clone)]
+pub struct GameErrorEvent {
+    pub id: TxId,
+    pub kind: TxKind,
+    pub error: GameError,
+}
+
+#[derive(Debug, Clone)]
+pub struct PendingTransaction {
+    pub kind: TxKind,
+    pub status: TxStatus,
+}
+
+/// Transactions that have not reached a final status yet.
+#[derive(Resource, Debug, Default)]
+pub struct PendingTransactions(pub HashMap<TxId, PendingTransaction>);
+
+#[derive(Resource)]
+pub struct TxUpdateChannel {
+    pub rx: mpsc::Receiver<TxStatusChanged>,
+}
+
+fn receive_tx_updates(
+    mut channel: ResMut<TxUpdateChannel>,
+    mut pending: ResMut<PendingTransactions>,
+    mut events: EventWriter<TxStatusChanged>,
+    mut game_errors: EventWriter<GameErrorEvent>,
+) {
+    while let Ok(update) = channel.rx.try_recv() {
+        info!(
+            "tx {:?} ({:?}): {:?}",
+            update.id, update.kind, update.status
+        );
+        let error = match &update.status {
+            TxStatus::Reverted { reason, .. } => Some(GameError::from_revert_reason(reason)),
+            TxStatus::SimulationFailed(reason) | TxStatus::Failed(reason) => {
+                GameError::find_in(reason)
+            }
+            _ => None,
+        };
+        if let Some(error) = error {
+            warn!("tx {:?} ({:?}) failed: {error}", update.id, update.kind);
+            game_errors.send(GameErrorEvent {
+                id: update.id,
+                kind: update.kind,
+                error,
+            });
+        }
+
+        if update.status.is_final() {
+            pending.0.remove(&update.id);
+        } else {
+            pending.0.insert(
+                update.id,
+                PendingTransaction {
+                    kind: update.kind,
+                    status: update.status.clone(),
+                },
+            );
+        }
+        events.send(update);
+    }
+}
+
+/// Polls the status of `hash` until it is final, reporting every change.
+pub async fn track_transaction(
+    provider: Arc<JsonRpcClient<HttpTransport>>,
+    id: TxId,
+    kind: TxKind,
+    hash: Felt,
+    updates: mpsc::Sender<TxStatusChanged>,
+) {
+    let report = |status: TxStatus| {
+        let updates = updates.clone();
+        async move {
+            let _ = updates.send(TxStatusChanged { id, kind, status }).await;
+        }
+    };
+
+    let mut last = Some(TxStatus::Received(hash));
+    for _ in 0..MAX_POLLS {
+        let status = match provider.get_transaction_status(hash).await {
+            Ok(TransactionStatus::Received) => TxStatus::Received(hash),
+            Ok(TransactionStatus::Rejected { .. }) => TxStatus::Rejected(hash),
+            Ok(TransactionStatus::AcceptedOnL2(execution))
+            | Ok(TransactionStatus::AcceptedOnL1(execution)) => {
+                if last != Some(TxStatus::AcceptedOnL2(hash)) {
+                    report(TxStatus::AcceptedOnL2(hash)).await;
+                }
+                match execution {
+                    TransactionExecutionStatus::Succeeded => TxStatus::Succeeded(hash),
+                    TransactionExecutionStatus::Reverted => TxStatus::Reverted {
+                        hash,
+                        reason: revert_reason(&provider, hash).await,
+                    },
+                }
+            }
+            Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+            Err(e) => {
+                warn!("cannot poll status of {hash:#x}: {e}");
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+
+        let is_final = status.is_final();
+        if last.as_ref() != Some(&status) {
+            report(status.clone()).await;
+            last = Some(status);
+        }
+        if is_final {
+            return;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    report(TxStatus::Failed(format!("gave up tracking {hash:#x}"))).await;
+}
+
+async fn revert_reason(provider: &JsonRpcClient<HttpTransport>, hash: Felt) -> String {
+    match provider.get_transaction_receipt(hash).await {
+        Ok(receipt) => match receipt.receipt.execution_result() {
+            ExecutionResult::Reverted { reason } => reason.clone(),
+            ExecutionResult::Succeeded => String::new(),
+        },
+        Err(e) => format!("cannot fetch receipt: {e}"),
+    }
+}
+
+/// Reports command thread progress and hands submitted transactions over
+/// to [`track_transaction`]. A transaction stays in `in_flight` until its
+/// status is final.
+#[derive(Clone)]
+pub struct TxTracker {
+    pub provider: Arc<JsonRpcClient<HttpTransport>>,
+    pub updates: mpsc::Sender<TxStatusChanged>,
+    pub in_flight: Arc<Mutex<HashSet<DedupKey>>>,
+}
+impl TxTracker {
+    pub async fn report(&self, id: TxId, kind: TxKind, status: TxStatus) {
+        let _ = self
+            .updates
+            .send(TxStatusChanged { id, kind, status })
+            .await;
+    }
+
+    pub async fn submitted(
+        &self,
+        id: TxId,
+        kind: TxKind,
+        key: DedupKey,
+        tx: InvokeTransactionResult,
+    ) {
+        self.report(id, kind, TxStatus::Received(tx.transaction_hash))
+            .await;
+
+        self.in_flight.lock().unwrap().insert(key.clone());
+        let provider = self.provider.clone();
+        let updates = self.updates.clone();
+        let in_flight = self.in_flight.clone();
+        tokio::spawn(async move {
+            track_transaction(provider, id, kind, tx.transaction_hash, updates).await;
+            in_flight.lock().unwrap().remove(&key);
+        });
+    }
+
+    /// Reports a transaction that never reached the chain. Only a revert
+    /// raised while `estimating` means the simulation reverted; the node
+    /// failing during the estimation is a plain failure.
+    pub async fn failed(&self, id: TxId, kind: TxKind, error: &SendError, estimating: bool) {
+        let status = match error.revert_reason() {
+            Some(reason) if estimating => TxStatus::SimulationFailed(reason),
+            Some(reason) => TxStatus::Failed(reason),
+            None => TxStatus::Failed(error.to_string()),
+        };
+        self.report(id, kind, status).await;
+    }
+}
@@ -4,8 +4,8 @@ use bevy::prelude::*;
 use super::constants::{XMUL, YMUL};
 use super::tile::Tile;
 
-// Import the ClaimTilePosition resource from the networking module
-use crate::plugins::networking::starknet_call::ClaimTilePosition;
+// Import the claim tile resource from the networking module
+use crate::plugins::networking::starknet_call::{ClaimTilePosition, GridPos};
 
 pub struct HighlightPlugin;
 impl Plugin for HighlightPlugin {
@@ -43,38 +43,14 @@ fn update_claim_tile_position(
     if let Some(highlighted_entity) = highlight_state.highlighted_entity {
         // Get the tile component to access grid coordinates
         if let Ok(tile) = highlightables.get(highlighted_entity) {
-            // Convert grid coordinates to static strings that can be used by the Starknet call
-            // We need to convert the i32 grid coordinates to static strings
-            let x_str = match tile.grid_x {
-                0 => "0",
-                1 => "1",
-                2 => "2",
-                3 => "3",
-                4 => "4",
-                5 => "5",
-                6 => "6",
-                7 => "7",
-                _ => "0", // Default to 0 for any other value
+            let pos = GridPos {
+                x: tile.grid_x as u8,
+                y: tile.grid_y as u8,
             };
-
-            let y_str = match tile.grid_y {
-                0 => "0",
-                1 => "1",
-                2 => "2",
-                3 => "3",
-                4 => "4",
-                5 => "5",
-                6 => "6",
-                7 => "7",
-                _ => "0", // Default to 0 for any other value
-            };
-
-            // Update the ClaimTilePosition resource
-            claim_tile_position.x = x_str;
-            claim_tile_position.y = y_str;
+            claim_tile_position.pos = pos;
 
             // Log the update for debugging
-            info!("Updated claim tile position to ({}, {})", x_str, y_str);
+            info!("Updated claim tile position to ({}, {})", pos.x, pos.y);
         }
     }
 }
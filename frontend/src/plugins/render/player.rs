@@ -1,8 +1,13 @@
-use bevy::{asset::Handle, prelude::*};
+use std::collections::HashSet;
+
+use bevy::prelude::*;
 
 use crate::plugins::{
-    dojo_models::{Game, Tile},
-    networking::torii::BevyFelt,
+    dojo_models::{events::TileClaimed, Game, Tile},
+    networking::{
+        starknet_call::{ActiveGame, GridPos},
+        torii::BevyFelt,
+    },
 };
 
 use super::assets::AllAssetHandles;
@@ -10,8 +15,9 @@ use super::assets::AllAssetHandles;
 pub struct RPlayerPlugin;
 impl Plugin for RPlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, spawn_player_markers.run_if(is_game_ready));
+        app.add_systems(Update, spawn_player_markers);
         app.add_systems(Update, animate_markers);
+        app.add_systems(Update, despawn_orphan_markers);
     }
 }
 
@@ -30,60 +36,105 @@ pub struct DerivePlayerPosition {
     pub y: u8,
 }
 
-fn is_game_ready(game_query: Query<&Game>) -> bool {
-    // game_query.iter().count() > 0
-    if let Some(_game) = game_query.iter().find(|g| g.game_id == 1) {
-        true
-    } else {
-        false
-    }
-}
-
 #[derive(Component, Reflect)]
 pub struct RenderedPlayerMarker {
+    pub game_id: u128,
     pub x: u8,
     pub y: u8,
 }
 
+/// Spawns a marker for every tile claimed in the active game. Claims are
+/// only sent once, so when the active game changes, or first shows up
+/// after its tiles, the markers are rebuilt from the synced tiles instead.
 fn spawn_player_markers(
     mut commands: Commands,
     asset_handler: Res<AllAssetHandles>,
+    mut claimed: EventReader<TileClaimed>,
+    game_query: Query<Ref<Game>>,
     tile_query: Query<&Tile>,
-    game_query: Query<&Game>,
-    render_marker_query: Query<&mut RenderedPlayerMarker>,
+    active_game: Option<Res<ActiveGame>>,
+    marker_query: Query<(Entity, &RenderedPlayerMarker)>,
 ) {
-    let game = game_query.iter().find(|g| g.game_id == 1).unwrap();
-    let p1 = game.player_one.clone();
-    let _p2 = game.player_two.clone();
+    let claims: Vec<TileClaimed> = claimed.read().cloned().collect();
+    let Some(active_game) = active_game else {
+        return;
+    };
+    let game_id = active_game.game_id;
+    let Some(game) = game_query.iter().find(|g| g.game_id == game_id) else {
+        return;
+    };
 
-    for tile in tile_query.iter() {
-        if let None = render_marker_query
-            .iter()
-            .find(|m| m.x == tile.x && m.y == tile.y)
-        {
-            let marker_handle: Handle<Scene>;
-            if p1 == tile.owner {
-                marker_handle = asset_handler.blade.clone();
-            } else {
-                marker_handle = asset_handler.shield.clone();
+    let claims: Vec<(GridPos, BevyFelt)> = if active_game.is_changed() || game.is_added() {
+        for (entity, marker) in marker_query.iter() {
+            if marker.game_id != game_id {
+                commands.entity(entity).despawn_recursive();
             }
+        }
+        tile_query
+            .iter()
+            .filter(|tile| tile.game_id == game_id)
+            .map(|tile| (GridPos::new(tile.x, tile.y), tile.owner))
+            .collect()
+    } else {
+        claims
+            .into_iter()
+            .filter(|claim| claim.game_id == game_id)
+            .map(|claim| (claim.pos, claim.owner))
+            .collect()
+    };
+    let mut rendered: HashSet<GridPos> = marker_query
+        .iter()
+        .filter(|(_, marker)| marker.game_id == game_id)
+        .map(|(_, marker)| GridPos::new(marker.x, marker.y))
+        .collect();
+
+    for (pos, owner) in claims {
+        if !rendered.insert(pos) {
+            continue;
+        }
+        let marker_handle = if owner == game.player_one {
+            asset_handler.blade.clone()
+        } else {
+            asset_handler.shield.clone()
+        };
+
+        let marker_scene = SceneRoot(marker_handle);
+        let transform = Transform::from_xyz(
+            (pos.x as f32 * X_MUL) + X_DIFF,
+            (pos.y as f32 * Y_MUL) + Y_DIFF,
+            Z_HEIGHT,
+        )
+        .with_scale(Vec3::splat(SCALE))
+        .with_rotation(Quat::from_axis_angle(Vec3::X, ANGLE_90));
+        commands.spawn((
+            marker_scene,
+            transform,
+            RenderedPlayerMarker {
+                game_id,
+                x: pos.x,
+                y: pos.y,
+            },
+        ));
+    }
+}
+
+/// Despawns the markers of tiles that were removed.
+fn despawn_orphan_markers(
+    mut commands: Commands,
+    mut removed_tiles: RemovedComponents<Tile>,
+    tile_query: Query<&Tile>,
+    marker_query: Query<(Entity, &RenderedPlayerMarker)>,
+) {
+    if removed_tiles.read().count() == 0 {
+        return;
+    }
 
-            let marker_scene = SceneRoot(marker_handle);
-            let transform = Transform::from_xyz(
-                (tile.x as f32 * X_MUL) + X_DIFF,
-                (tile.y as f32 * Y_MUL) + Y_DIFF,
-                Z_HEIGHT,
-            )
-            .with_scale(Vec3::splat(SCALE))
-            .with_rotation(Quat::from_axis_angle(Vec3::X, ANGLE_90));
-            commands.spawn((
-                marker_scene,
-                transform,
-                RenderedPlayerMarker {
-                    x: tile.x,
-                    y: tile.y,
-                },
-            ));
+    for (entity, marker) in marker_query.iter() {
+        if !tile_query
+            .iter()
+            .any(|tile| tile.game_id == marker.game_id && tile.x == marker.x && tile.y == marker.y)
+        {
+            commands.entity(entity).despawn_recursive();
         }
     }
 }
@@ -93,3 +144,42 @@ fn animate_markers(mut query: Query<&mut Transform, With<RenderedPlayerMarker>>,
         marker.rotate_z(0.5 * time.delta_secs());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use starknet::core::types::Felt;
+
+    use super::*;
+
+    fn tile(game_id: u128, x: u8, y: u8) -> Tile {
+        Tile {
+            game_id,
+            x,
+            y,
+            owner: BevyFelt(Felt::ONE),
+        }
+    }
+
+    fn marker(game_id: u128, x: u8, y: u8) -> RenderedPlayerMarker {
+        RenderedPlayerMarker { game_id, x, y }
+    }
+
+    #[test]
+    fn despawns_the_marker_of_a_removed_tile() {
+        let mut app = App::new();
+        app.add_systems(Update, despawn_orphan_markers);
+        let removed = app.world_mut().spawn(tile(1, 2, 3)).id();
+        app.world_mut().spawn(tile(1, 4, 4));
+        // The same tile of another game keeps nothing of game 1 alive.
+        app.world_mut().spawn(tile(2, 2, 3));
+        let orphan = app.world_mut().spawn(marker(1, 2, 3)).id();
+        let kept = app.world_mut().spawn(marker(1, 4, 4)).id();
+        app.update();
+
+        app.world_mut().entity_mut(removed).remove::<Tile>();
+        app.update();
+
+        assert!(!app.world().entities().contains(orphan));
+        assert!(app.world().entities().contains(kept));
+    }
+}
@@ -14,6 +14,11 @@ impl Plugin for RTilePlugin {
             .add_systems(
                 OnEnter(GameState::Playing),
                 make_tiles_highlightable.after(spawn_tiles),
+            )
+            .add_systems(OnEnter(GameState::Local), spawn_tiles)
+            .add_systems(
+                OnEnter(GameState::Local),
+                make_tiles_highlightable.after(spawn_tiles),
             );
     }
 }
@@ -25,7 +30,15 @@ pub struct Tile {
     pub grid_y: u32,
 }
 
-fn spawn_tiles(mut commands: Commands, asset_handler: Res<AllAssetHandles>) {
+fn spawn_tiles(
+    mut commands: Commands,
+    asset_handler: Res<AllAssetHandles>,
+    tiles: Query<(), With<Tile>>,
+) {
+    // The board stays up when switching between online and hot-seat play.
+    if !tiles.is_empty() {
+        return;
+    }
     let grid_array = (8, 8);
     for i in 0..grid_array.0 {
         for j in 0..grid_array.1 {
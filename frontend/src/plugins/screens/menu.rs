@@ -71,6 +71,30 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>) {
                     },
                     TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
                 ));
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(200.0),
+                        height: Val::Px(50.0),
+                        margin: UiRect::top(Val::Px(10.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    BackgroundColor(button_colors.normal),
+                    button_colors,
+                    ChangeState(GameState::Local),
+                ))
+                .with_child((
+                    Text::new("Hot seat"),
+                    TextFont {
+                        font_size: 40.0,
+                        ..default()
+                    },
+                    TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
+                ));
         });
     commands
         .spawn((
@@ -1,15 +1,17 @@
 pub mod loading;
 mod menu;
+mod network;
 
 use crate::GameState;
 use bevy::prelude::*;
 use loading::LoadingPlugin;
 use menu::MenuPlugin;
+use network::NetworkStatusPlugin;
 
 pub struct GridGuruScreenPlugin;
 impl Plugin for GridGuruScreenPlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<GameState>()
-            .add_plugins((LoadingPlugin, MenuPlugin));
+            .add_plugins((LoadingPlugin, MenuPlugin, NetworkStatusPlugin));
     }
 }
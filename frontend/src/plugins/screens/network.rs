@@ -0,0 +1,56 @@
+use crate::plugins::networking::NetworkFailure;
+use bevy::prelude::*;
+
+pub struct NetworkStatusPlugin;
+
+/// Tells the player why online play is unavailable, until the failure is
+/// cleared.
+impl Plugin for NetworkStatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            show_network_failure.run_if(resource_exists_and_changed::<NetworkFailure>),
+        )
+        .add_systems(
+            Update,
+            hide_network_failure.run_if(resource_removed::<NetworkFailure>),
+        );
+    }
+}
+
+#[derive(Component)]
+struct NetworkFailureText;
+
+fn show_network_failure(
+    mut commands: Commands,
+    failure: Res<NetworkFailure>,
+    mut texts: Query<&mut Text, With<NetworkFailureText>>,
+) {
+    let message = format!("Offline: {}", failure.0);
+    if let Ok(mut text) = texts.get_single_mut() {
+        text.0 = message;
+        return;
+    }
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.),
+            left: Val::Px(5.),
+            ..default()
+        },
+        Text::new(message),
+        TextFont {
+            font_size: 15.0,
+            ..default()
+        },
+        TextColor(Color::linear_rgb(0.9, 0.3, 0.3)),
+        NetworkFailureText,
+    ));
+}
+
+fn hide_network_failure(mut commands: Commands, texts: Query<Entity, With<NetworkFailureText>>) {
+    for entity in texts.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,185 @@
+//! Computer opponent. Easy plays at random, Medium and Hard search the
+//! game tree with alpha-beta pruning.
+
+use std::{fmt, str::FromStr};
+
+use rand::{seq::SliceRandom, Rng};
+
+use super::{GameState, GridPos, Side};
+
+/// Score of a won position, before the distance penalty.
+const WIN: i32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    /// How many moves ahead the search looks.
+    fn depth(self) -> u8 {
+        match self {
+            Difficulty::Easy => 0,
+            Difficulty::Medium => 2,
+            Difficulty::Hard => 6,
+        }
+    }
+}
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Medium => write!(f, "medium"),
+            Difficulty::Hard => write!(f, "hard"),
+        }
+    }
+}
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Difficulty::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.to_string() == s)
+            .ok_or_else(|| format!("unknown difficulty '{s}'"))
+    }
+}
+
+/// The tile the current player should claim, or `None` if they cannot
+/// claim any. Equally good moves are picked at random.
+pub fn choose_move(
+    state: &GameState,
+    difficulty: Difficulty,
+    rng: &mut impl Rng,
+) -> Option<GridPos> {
+    let mut moves: Vec<GridPos> = state.legal_moves().iter().collect();
+    moves.shuffle(rng);
+    if difficulty == Difficulty::Easy {
+        return moves.first().copied();
+    }
+
+    let side = state.current;
+    let mut best = None;
+    let mut alpha = -WIN - 1;
+    for pos in moves {
+        let next = state.apply_move(pos).expect("legal moves can be applied");
+        let score = -negamax(
+            &next,
+            side.opponent(),
+            difficulty.depth() - 1,
+            -WIN - 1,
+            -alpha,
+            1,
+        );
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some(pos);
+        }
+    }
+    best
+}
+
+/// Value of `state` for `side`, who is to move. Losing sooner and winning
+/// later score worse, so the search goes for the quickest win.
+fn negamax(state: &GameState, side: Side, depth: u8, mut alpha: i32, beta: i32, ply: i32) -> i32 {
+    if let Some(winner) = state.winner {
+        return if winner == side { WIN - ply } else { ply - WIN };
+    }
+    // The contract never ends a game whose player to move is walled in,
+    // but they cannot claim anything again, which is as good as lost.
+    let moves = state.legal_moves();
+    if moves.is_empty() {
+        return ply - WIN;
+    }
+    if depth == 0 {
+        return evaluate(state, side);
+    }
+
+    for pos in moves.iter() {
+        let next = state.apply_move(pos).expect("legal moves can be applied");
+        let score = -negamax(&next, side.opponent(), depth - 1, -beta, -alpha, ply + 1);
+        if score >= beta {
+            return score;
+        }
+        alpha = alpha.max(score);
+    }
+    alpha
+}
+
+/// Favours having more room to grow than the opponent.
+fn evaluate(state: &GameState, side: Side) -> i32 {
+    let room = |side: Side| {
+        let moves = state.remaining_moves(side);
+        2 * moves.count() as i32 + moves.neighbours().count() as i32
+    };
+    room(side) - room(side.opponent())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::rules::Bitboard;
+
+    fn board(tiles: &[(u8, u8)]) -> Bitboard {
+        tiles.iter().fold(Bitboard::EMPTY, |board, (x, y)| {
+            board.with(GridPos::new(*x, *y))
+        })
+    }
+
+    #[test]
+    fn every_level_plays_a_legal_move() {
+        let state = GameState::new();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for difficulty in Difficulty::ALL {
+            let pos = choose_move(&state, difficulty, &mut rng).unwrap();
+            assert!(
+                state.legal_moves().contains(pos),
+                "{difficulty} played {pos:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn walls_in_the_opponent() {
+        // Player two's only way out of the corner is (7, 6).
+        let state = GameState::from_grids(
+            [board(&[(7, 5), (6, 5), (6, 6), (6, 7)]), board(&[(7, 7)])],
+            [GridPos::new(7, 5), GridPos::new(7, 7)],
+            Side::One,
+        );
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for difficulty in [Difficulty::Medium, Difficulty::Hard] {
+            assert_eq!(
+                choose_move(&state, difficulty, &mut rng),
+                Some(GridPos::new(7, 6))
+            );
+        }
+    }
+
+    #[test]
+    fn passes_without_legal_moves() {
+        let state = GameState::from_grids(
+            [board(&[(0, 0)]), board(&[(1, 0), (0, 1)])],
+            [GridPos::new(0, 0), GridPos::new(1, 0)],
+            Side::One,
+        );
+
+        assert_eq!(
+            choose_move(&state, Difficulty::Hard, &mut StdRng::seed_from_u64(7)),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_difficulties() {
+        assert_eq!("hard".parse(), Ok(Difficulty::Hard));
+        assert!("impossible".parse::<Difficulty>().is_err());
+    }
+}
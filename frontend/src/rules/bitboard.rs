@@ -0,0 +1,197 @@
+use std::fmt;
+
+use starknet::core::types::Felt;
+
+use super::{GridPos, BOARD_SIZE};
+
+/// Tiles with `x == 7`, which have no neighbour at `x + 1`.
+const LAST_COLUMN: u64 = 0x0101_0101_0101_0101;
+/// Tiles with `x == 0`, which have no neighbour at `x - 1`.
+const FIRST_COLUMN: u64 = 0x8080_8080_8080_8080;
+
+/// The 64-tile occupancy bitmap stored in `Player.grid`. Tile `(x, y)` is
+/// bit `(7 - x) + (7 - y) * 8`, as in `PlayerTrait::move`, so `(0, 0)` is
+/// the most significant bit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const FULL: Bitboard = Bitboard(u64::MAX);
+
+    /// The bit index of `pos`, or `None` if it is off the board.
+    pub fn index(pos: GridPos) -> Option<u8> {
+        pos.in_bounds()
+            .then(|| (BOARD_SIZE - 1 - pos.x) + (BOARD_SIZE - 1 - pos.y) * BOARD_SIZE)
+    }
+
+    /// The tile of bit `index`, which must be below 64.
+    pub fn pos(index: u8) -> GridPos {
+        GridPos {
+            x: BOARD_SIZE - 1 - index % BOARD_SIZE,
+            y: BOARD_SIZE - 1 - index / BOARD_SIZE,
+        }
+    }
+
+    /// Reads a `grid` felt. Felts wider than 64 bits are not bitmaps.
+    pub fn from_felt(felt: Felt) -> Option<Bitboard> {
+        u64::try_from(felt).ok().map(Bitboard)
+    }
+
+    pub fn to_felt(self) -> Felt {
+        Felt::from(self.0)
+    }
+
+    pub fn single(pos: GridPos) -> Bitboard {
+        Bitboard::EMPTY.with(pos)
+    }
+
+    pub fn contains(self, pos: GridPos) -> bool {
+        Bitboard::index(pos).is_some_and(|index| self.0 >> index & 1 == 1)
+    }
+
+    /// This board with `pos` set. Positions off the board are ignored.
+    pub fn with(self, pos: GridPos) -> Bitboard {
+        match Bitboard::index(pos) {
+            Some(index) => Bitboard(self.0 | 1 << index),
+            None => self,
+        }
+    }
+
+    pub fn without(self, pos: GridPos) -> Bitboard {
+        match Bitboard::index(pos) {
+            Some(index) => Bitboard(self.0 & !(1 << index)),
+            None => self,
+        }
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The set tiles, from the lowest bit index up.
+    pub fn iter(self) -> impl Iterator<Item = GridPos> {
+        (0..64u8)
+            .filter(move |index| self.0 >> index & 1 == 1)
+            .map(Bitboard::pos)
+    }
+
+    /// Tiles orthogonally next to a set tile, excluding the set tiles.
+    pub fn neighbours(self) -> Bitboard {
+        let b = self.0;
+        let spread = (b & !LAST_COLUMN) >> 1 | (b & !FIRST_COLUMN) << 1 | b >> 8 | b << 8;
+        Bitboard(spread & !b)
+    }
+
+    /// The tiles of this board connected to `seed` through orthogonal steps.
+    pub fn flood_fill(self, seed: GridPos) -> Bitboard {
+        if !self.contains(seed) {
+            return Bitboard::EMPTY;
+        }
+
+        let mut filled = Bitboard::single(seed);
+        loop {
+            let next = Bitboard(filled.0 | filled.neighbours().0 & self.0);
+            if next == filled {
+                return filled;
+            }
+            filled = next;
+        }
+    }
+}
+
+impl std::ops::BitOr for Bitboard {
+    type Output = Bitboard;
+
+    fn bitor(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitAnd for Bitboard {
+    type Output = Bitboard;
+
+    fn bitand(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 & rhs.0)
+    }
+}
+
+impl std::ops::Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Bitboard {
+        Bitboard(!self.0)
+    }
+}
+
+/// Draws the board with `y = 0` on the first line and `x = 0` on the left.
+impl fmt::Display for Bitboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                let tile = if self.contains(GridPos { x, y }) {
+                    '#'
+                } else {
+                    '.'
+                };
+                write!(f, "{tile}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_cairo_start_grids() {
+        assert_eq!(Bitboard::single(GridPos::new(0, 0)).0, 0x8000000000000000);
+        assert_eq!(Bitboard::single(GridPos::new(7, 7)).0, 0x1);
+        assert_eq!(Bitboard::index(GridPos::new(8, 0)), None);
+    }
+
+    #[test]
+    fn maps_indices_back_to_positions() {
+        for index in 0..64 {
+            assert_eq!(Bitboard::index(Bitboard::pos(index)), Some(index));
+        }
+    }
+
+    #[test]
+    fn finds_neighbours_within_the_board() {
+        let corner = Bitboard::single(GridPos::new(0, 0));
+        let expected = Bitboard::single(GridPos::new(1, 0)) | Bitboard::single(GridPos::new(0, 1));
+        assert_eq!(corner.neighbours(), expected);
+
+        let edge = Bitboard::single(GridPos::new(7, 3));
+        assert_eq!(edge.neighbours().count(), 3);
+        assert!(!edge.neighbours().contains(GridPos::new(0, 4)));
+    }
+
+    #[test]
+    fn flood_fills_connected_tiles_only() {
+        let board = Bitboard::single(GridPos::new(0, 0))
+            .with(GridPos::new(1, 0))
+            .with(GridPos::new(1, 1))
+            .with(GridPos::new(3, 3));
+
+        let filled = board.flood_fill(GridPos::new(0, 0));
+
+        assert_eq!(filled.count(), 3);
+        assert!(!filled.contains(GridPos::new(3, 3)));
+    }
+
+    #[test]
+    fn round_trips_through_felts() {
+        let board = Bitboard(0x8000000000000001);
+        assert_eq!(Bitboard::from_felt(board.to_felt()), Some(board));
+        assert_eq!(Bitboard::from_felt(Felt::from(u128::MAX)), None);
+    }
+}
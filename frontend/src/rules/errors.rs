@@ -0,0 +1,208 @@
+use std::fmt;
+
+use starknet::core::{types::Felt, utils::parse_cairo_short_string};
+
+/// Assertion failures of the Cairo contracts, keyed by their short string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameError {
+    ClaimedTile,
+    OpponentTile,
+    NoPathToTarget,
+    XOutOfBounds,
+    YOutOfBounds,
+    GameNotInProgress,
+    GameNotPending,
+    CannotPlaySelf,
+    InvalidPlayerAddress,
+    TileAlreadyClaimed,
+    OutOfBounds,
+    NoAdjacentTile,
+    NotPlayerTurn,
+    /// A revert that is not one of the game's own assertions.
+    Other(String),
+}
+
+const KNOWN_ERRORS: [GameError; 13] = [
+    GameError::ClaimedTile,
+    GameError::OpponentTile,
+    GameError::NoPathToTarget,
+    GameError::XOutOfBounds,
+    GameError::YOutOfBounds,
+    GameError::GameNotInProgress,
+    GameError::GameNotPending,
+    GameError::CannotPlaySelf,
+    GameError::InvalidPlayerAddress,
+    GameError::TileAlreadyClaimed,
+    GameError::OutOfBounds,
+    GameError::NoAdjacentTile,
+    GameError::NotPlayerTurn,
+];
+
+impl GameError {
+    /// The short string the contracts revert with.
+    pub fn felt_message(&self) -> &str {
+        match self {
+            GameError::ClaimedTile => "Claimed tile",
+            GameError::OpponentTile => "Tile claimed by opponent",
+            GameError::NoPathToTarget => "No path to target",
+            GameError::XOutOfBounds => "x out of bounds",
+            GameError::YOutOfBounds => "y out of bounds",
+            GameError::GameNotInProgress => "Game: not in progress",
+            GameError::GameNotPending => "Game: not pending",
+            GameError::CannotPlaySelf => "Game: Cannot play self",
+            GameError::InvalidPlayerAddress => "Game: invalid player address",
+            GameError::TileAlreadyClaimed => "Tile already claimed",
+            GameError::OutOfBounds => "Out of bounds",
+            GameError::NoAdjacentTile => "No adjacent tile owned",
+            GameError::NotPlayerTurn => "Not player turn",
+            GameError::Other(message) => message,
+        }
+    }
+
+    pub fn from_felt(felt: Felt) -> Option<GameError> {
+        let message = parse_cairo_short_string(&felt).ok()?;
+        KNOWN_ERRORS
+            .into_iter()
+            .find(|e| e.felt_message() == message)
+    }
+
+    /// Looks for a known error in a revert trace, either as the decoded
+    /// `('...')` text or as a raw hex felt.
+    pub fn find_in(text: &str) -> Option<GameError> {
+        if let Some(error) = KNOWN_ERRORS
+            .into_iter()
+            .find(|e| text.contains(&format!("'{}'", e.felt_message())))
+        {
+            return Some(error);
+        }
+
+        text.split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| word.starts_with("0x"))
+            .filter_map(|word| Felt::from_hex(word).ok())
+            .find_map(GameError::from_felt)
+    }
+
+    /// Maps the revert reason of a transaction to a `GameError`, keeping
+    /// the last line of unknown reasons.
+    pub fn from_revert_reason(reason: &str) -> GameError {
+        GameError::find_in(reason).unwrap_or_else(|| {
+            let line = reason.trim().lines().last().unwrap_or_default();
+            GameError::Other(line.trim().to_string())
+        })
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::ClaimedTile | GameError::TileAlreadyClaimed => {
+                write!(f, "you already claimed that tile")
+            }
+            GameError::OpponentTile => write!(f, "that tile belongs to your opponent"),
+            GameError::NoPathToTarget | GameError::NoAdjacentTile => {
+                write!(f, "that tile is not connected to your territory")
+            }
+            GameError::XOutOfBounds | GameError::YOutOfBounds | GameError::OutOfBounds => {
+                write!(f, "that tile is outside the board")
+            }
+            GameError::GameNotInProgress => write!(f, "the game is not in progress"),
+            GameError::GameNotPending => write!(f, "the game is not waiting for a player"),
+            GameError::CannotPlaySelf => write!(f, "you cannot join your own game"),
+            GameError::InvalidPlayerAddress => write!(f, "invalid player address"),
+            GameError::NotPlayerTurn => write!(f, "it is not your turn"),
+            GameError::Other(message) => write!(f, "transaction reverted: {message}"),
+        }
+    }
+}
+impl std::error::Error for GameError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACTIONS: &str = "0x4e8ca0d54f13a6718001bdbb61e4bcaf69bf0985229a03bdd4db7701a1af9f5";
+
+    /// `revert_reason` of a reverted receipt, as Katana reports it.
+    fn receipt_reason(failure: &str) -> String {
+        format!(
+            "Error in the called contract ({ACTIONS}):\n\
+             Error at pc=0:4835:\n\
+             Cairo traceback (most recent call last):\n\
+             Unknown location (pc=0:67)\n\
+             Unknown location (pc=0:1842)\n\
+             \n\
+             Error in the called contract ({ACTIONS}):\n\
+             Execution failed. Failure reason: {failure}.\n"
+        )
+    }
+
+    /// `execution_error` of the `TransactionExecutionError` an
+    /// `AccountError::Provider` carries when fee estimation fails.
+    fn estimate_error(failure: &str) -> String {
+        format!(
+            "Transaction execution has failed:\n\
+             0: Error in the called contract (contract address: 0x127fd5f1fe78a71f8bcd1fec63e3fe2f0486b6ecd5c86a0466c3a21fa5cfcec, \
+             class hash: 0x07dc7899aa655b0aae51eadff6d801a58e97dd99cf4666ee59e704249e51adf2, \
+             selector: 0x015d40a3d6ca2ac30f4031e42be28da9b056fef9bb7357ac5e85627ee876e5ad):\n\
+             Execution failed. Failure reason: (0x{entrypoint_failed} ('ENTRYPOINT_FAILED'), {failure}).\n",
+            entrypoint_failed = "454e545259504f494e545f4641494c4544",
+        )
+    }
+
+    #[test]
+    fn maps_each_revert_of_the_actions_contract() {
+        let reverts = [
+            (
+                "0x436c61696d65642074696c65 ('Claimed tile')",
+                GameError::ClaimedTile,
+            ),
+            (
+                "0x54696c6520636c61696d6564206279206f70706f6e656e74 ('Tile claimed by opponent')",
+                GameError::OpponentTile,
+            ),
+            (
+                "0x4e6f207061746820746f20746172676574 ('No path to target')",
+                GameError::NoPathToTarget,
+            ),
+            (
+                "0x47616d653a206e6f742070656e64696e67 ('Game: not pending')",
+                GameError::GameNotPending,
+            ),
+            (
+                "0x47616d653a2043616e6e6f7420706c61792073656c66 ('Game: Cannot play self')",
+                GameError::CannotPlaySelf,
+            ),
+        ];
+
+        for (failure, expected) in reverts {
+            assert_eq!(
+                GameError::from_revert_reason(&receipt_reason(failure)),
+                expected
+            );
+            assert_eq!(GameError::find_in(&estimate_error(failure)), Some(expected));
+        }
+    }
+
+    #[test]
+    fn finds_an_undecoded_felt() {
+        let reason = receipt_reason("0x4e6f207061746820746f20746172676574");
+
+        assert_eq!(GameError::find_in(&reason), Some(GameError::NoPathToTarget));
+    }
+
+    #[test]
+    fn does_not_map_unknown_reverts() {
+        let reason = receipt_reason("0x753235365f737562204f766572666c6f77 ('u256_sub Overflow')");
+
+        assert_eq!(GameError::find_in(&reason), None);
+        assert_eq!(
+            GameError::from_revert_reason(&reason),
+            GameError::Other(
+                "Execution failed. Failure reason: \
+                 0x753235365f737562204f766572666c6f77 ('u256_sub Overflow')."
+                    .to_string()
+            )
+        );
+        assert_eq!(GameError::find_in("Invalid transaction nonce"), None);
+    }
+}
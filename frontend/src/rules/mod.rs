@@ -0,0 +1,34 @@
+//! Grid Guru's game rules, mirroring the Cairo contracts so the client can
+//! reason about the board without a round trip to the chain.
+
+pub mod ai;
+pub mod bitboard;
+pub mod errors;
+pub mod path;
+pub mod state;
+
+use bevy::prelude::*;
+
+pub use bitboard::Bitboard;
+pub use errors::GameError;
+pub use path::search_path;
+pub use state::{GameState, GameStatus, Side};
+
+/// Width and height of the board.
+pub const BOARD_SIZE: u8 = 8;
+
+/// A tile coordinate on the board.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect)]
+pub struct GridPos {
+    pub x: u8,
+    pub y: u8,
+}
+impl GridPos {
+    pub fn new(x: u8, y: u8) -> GridPos {
+        GridPos { x, y }
+    }
+
+    pub fn in_bounds(&self) -> bool {
+        self.x < BOARD_SIZE && self.y < BOARD_SIZE
+    }
+}
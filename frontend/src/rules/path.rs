@@ -0,0 +1,119 @@
+//! Port of the `origami_map` path search `PlayerTrait::move` runs over a
+//! player's grid before accepting a claim.
+
+use std::collections::VecDeque;
+
+use super::{Bitboard, GridPos};
+
+/// A shortest path from `from` to `to` stepping orthogonally over the set
+/// tiles of `walkable`. The path excludes `from` and ends with `to`.
+///
+/// Returns `None` exactly when `search_path` on the contract side returns an
+/// empty span, which includes `from == to`. Both find a shortest path, but
+/// may pick a different one of equal length, and origami lists it from the
+/// target backwards.
+pub fn search_path(walkable: Bitboard, from: GridPos, to: GridPos) -> Option<Vec<GridPos>> {
+    let (Some(start), Some(target)) = (Bitboard::index(from), Bitboard::index(to)) else {
+        return None;
+    };
+    if start == target || !walkable.contains(from) || !walkable.contains(to) {
+        return None;
+    }
+
+    let mut parents = [None::<u8>; 64];
+    let mut visited = Bitboard::single(from);
+    let mut queue = VecDeque::from([start]);
+    while let Some(index) = queue.pop_front() {
+        if index == target {
+            return Some(walk_back(&parents, start, target));
+        }
+
+        let reachable = Bitboard::single(Bitboard::pos(index)).neighbours() & walkable & !visited;
+        for next in reachable.iter() {
+            visited = visited.with(next);
+            let next = Bitboard::index(next).expect("neighbours are on the board");
+            parents[next as usize] = Some(index);
+            queue.push_back(next);
+        }
+    }
+    None
+}
+
+fn walk_back(parents: &[Option<u8>; 64], start: u8, target: u8) -> Vec<GridPos> {
+    let mut path = Vec::new();
+    let mut index = target;
+    while index != start {
+        path.push(Bitboard::pos(index));
+        index = parents[index as usize].expect("visited tiles have a parent");
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(tiles: &[(u8, u8)]) -> Bitboard {
+        tiles.iter().fold(Bitboard::EMPTY, |board, (x, y)| {
+            board.with(GridPos::new(*x, *y))
+        })
+    }
+
+    #[test]
+    fn follows_the_walkable_tiles() {
+        let walkable = board(&[(0, 0), (1, 0), (1, 1), (1, 2), (2, 2)]);
+
+        let path = search_path(walkable, GridPos::new(0, 0), GridPos::new(2, 2)).unwrap();
+
+        assert_eq!(
+            path,
+            [(1, 0), (1, 1), (1, 2), (2, 2)].map(|(x, y)| GridPos::new(x, y))
+        );
+    }
+
+    #[test]
+    fn finds_a_shortest_path() {
+        let walkable = Bitboard::FULL;
+
+        let path = search_path(walkable, GridPos::new(0, 0), GridPos::new(7, 7)).unwrap();
+
+        assert_eq!(path.len(), 14);
+        assert_eq!(path.last(), Some(&GridPos::new(7, 7)));
+    }
+
+    #[test]
+    fn fails_without_a_connection() {
+        let walkable = board(&[(0, 0), (1, 0), (3, 0)]);
+
+        assert_eq!(
+            search_path(walkable, GridPos::new(0, 0), GridPos::new(3, 0)),
+            None
+        );
+        assert_eq!(
+            search_path(walkable, GridPos::new(0, 0), GridPos::new(2, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_no_path_to_the_start() {
+        let walkable = board(&[(0, 0), (1, 0)]);
+
+        assert_eq!(
+            search_path(walkable, GridPos::new(0, 0), GridPos::new(0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn does_not_step_across_board_edges() {
+        // Index-wise (7, 0) and (0, 1) are neighbours, on the board they are not.
+        let walkable = board(&[(7, 0), (0, 1)]);
+
+        assert_eq!(
+            search_path(walkable, GridPos::new(7, 0), GridPos::new(0, 1)),
+            None
+        );
+    }
+}
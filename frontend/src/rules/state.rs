@@ -0,0 +1,281 @@
+use bevy::prelude::*;
+
+use super::{path::search_path, Bitboard, GameError, GridPos, BOARD_SIZE};
+
+/// Where a game stands, as the contracts' `GameStatus` enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum GameStatus {
+    Pending,
+    InProgress,
+    Completed,
+    Draw,
+    Abandoned,
+    TimedOut,
+}
+
+/// One of the two seats of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    One,
+    Two,
+}
+impl Side {
+    pub fn opponent(self) -> Side {
+        match self {
+            Side::One => Side::Two,
+            Side::Two => Side::One,
+        }
+    }
+
+    /// Index of this side in per-side arrays.
+    pub fn seat(self) -> usize {
+        match self {
+            Side::One => 0,
+            Side::Two => 1,
+        }
+    }
+
+    /// Where the contracts place this side when it creates or joins a game.
+    pub fn start(self) -> GridPos {
+        match self {
+            Side::One => GridPos::new(0, 0),
+            Side::Two => GridPos::new(BOARD_SIZE - 1, BOARD_SIZE - 1),
+        }
+    }
+}
+
+/// A game as the contracts see it: both grids, both positions and whose
+/// turn it is. Applying a move returns the next state, so states can be
+/// explored without touching the synced components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GameState {
+    grids: [Bitboard; 2],
+    positions: [GridPos; 2],
+    pub current: Side,
+    pub status: GameStatus,
+    pub winner: Option<Side>,
+    pub move_count: u8,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        GameState::new()
+    }
+}
+
+impl GameState {
+    /// A game right after the second player joined.
+    pub fn new() -> GameState {
+        GameState::pending()
+            .join()
+            .expect("a pending game can be joined")
+    }
+
+    /// A game right after `create_game`, waiting for player two.
+    pub fn pending() -> GameState {
+        GameState {
+            grids: [Bitboard::single(Side::One.start()), Bitboard::EMPTY],
+            positions: [Side::One.start(), Side::Two.start()],
+            current: Side::One,
+            status: GameStatus::Pending,
+            winner: None,
+            move_count: 1,
+        }
+    }
+
+    /// Seats player two, like `join_game`.
+    pub fn join(&self) -> Result<GameState, GameError> {
+        if self.status != GameStatus::Pending {
+            return Err(GameError::GameNotPending);
+        }
+
+        let mut next = *self;
+        next.grids[Side::Two.seat()] = Bitboard::single(Side::Two.start());
+        next.status = GameStatus::InProgress;
+        next.move_count += 1;
+        Ok(next)
+    }
+
+    /// A game in progress with the given grids and positions.
+    pub fn from_grids(grids: [Bitboard; 2], positions: [GridPos; 2], current: Side) -> GameState {
+        GameState {
+            grids,
+            positions,
+            current,
+            status: GameStatus::InProgress,
+            winner: None,
+            move_count: (grids[0].count() + grids[1].count()) as u8,
+        }
+    }
+
+    pub fn grid(&self, side: Side) -> Bitboard {
+        self.grids[side.seat()]
+    }
+
+    pub fn position(&self, side: Side) -> GridPos {
+        self.positions[side.seat()]
+    }
+
+    /// The side that owns `pos`, if any.
+    pub fn owner(&self, pos: GridPos) -> Option<Side> {
+        [Side::One, Side::Two]
+            .into_iter()
+            .find(|side| self.grid(*side).contains(pos))
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.status != GameStatus::InProgress && self.status != GameStatus::Pending
+    }
+
+    /// Tiles `side` could still claim, like `PlayerTrait::remaining_moves`:
+    /// unclaimed tiles next to its grid.
+    pub fn remaining_moves(&self, side: Side) -> Bitboard {
+        self.grid(side).neighbours() & !self.grid(side.opponent())
+    }
+
+    /// Tiles the current player can claim right now.
+    pub fn legal_moves(&self) -> Bitboard {
+        if self.status != GameStatus::InProgress {
+            return Bitboard::EMPTY;
+        }
+        self.remaining_moves(self.current)
+    }
+
+    /// The tiles the current player would walk from their position to
+    /// claim `pos`, as `PlayerTrait::move` searches them. `None` is the
+    /// 'No path to target' revert.
+    pub fn path_to(&self, pos: GridPos) -> Option<Vec<GridPos>> {
+        let side = self.current;
+        search_path(self.grid(side).with(pos), self.position(side), pos)
+    }
+
+    /// Claims `pos` for the current player, checking in the same order as
+    /// `claim_tile` and `PlayerTrait::move` so the error is the one the
+    /// transaction would revert with.
+    ///
+    /// The contract does not check whose turn it is; it toggles the turn
+    /// after every move, which is what the current player models.
+    pub fn apply_move(&self, pos: GridPos) -> Result<GameState, GameError> {
+        if self.status != GameStatus::InProgress {
+            return Err(GameError::GameNotInProgress);
+        }
+        if pos.x >= BOARD_SIZE {
+            return Err(GameError::XOutOfBounds);
+        }
+        if pos.y >= BOARD_SIZE {
+            return Err(GameError::YOutOfBounds);
+        }
+
+        let side = self.current;
+        if self.grid(side).contains(pos) {
+            return Err(GameError::ClaimedTile);
+        }
+        if self.grid(side.opponent()).contains(pos) {
+            return Err(GameError::OpponentTile);
+        }
+        if self.path_to(pos).is_none() {
+            return Err(GameError::NoPathToTarget);
+        }
+
+        let mut next = *self;
+        next.grids[side.seat()] = self.grid(side).with(pos);
+        next.positions[side.seat()] = pos;
+        next.move_count += 1;
+        if next.remaining_moves(side).is_empty() {
+            next.status = GameStatus::Completed;
+            next.winner = Some(side.opponent());
+        } else {
+            next.current = side.opponent();
+        }
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[(u8, u8)]) -> GameState {
+        moves.iter().fold(GameState::new(), |state, (x, y)| {
+            state.apply_move(GridPos::new(*x, *y)).unwrap()
+        })
+    }
+
+    #[test]
+    fn starts_from_the_contract_grids() {
+        let state = GameState::new();
+
+        assert_eq!(state.grid(Side::One).0, 0x8000000000000000);
+        assert_eq!(state.grid(Side::Two).0, 0x1);
+        assert_eq!(state.legal_moves().count(), 2);
+    }
+
+    #[test]
+    fn only_pending_games_can_be_joined() {
+        let pending = GameState::pending();
+
+        assert_eq!(
+            pending.apply_move(GridPos::new(1, 0)),
+            Err(GameError::GameNotInProgress)
+        );
+        assert_eq!(pending.join(), Ok(GameState::new()));
+        assert_eq!(GameState::new().join(), Err(GameError::GameNotPending));
+    }
+
+    #[test]
+    fn claims_adjacent_tiles_and_switches_turns() {
+        let state = play(&[(1, 0), (7, 6)]);
+
+        assert!(state.grid(Side::One).contains(GridPos::new(1, 0)));
+        assert_eq!(state.position(Side::Two), GridPos::new(7, 6));
+        assert_eq!(state.current, Side::One);
+        assert_eq!(state.move_count, 4);
+    }
+
+    #[test]
+    fn rejects_moves_with_the_contract_errors() {
+        let state = play(&[(1, 0), (7, 6)]);
+
+        let error = |x, y| state.apply_move(GridPos::new(x, y)).unwrap_err();
+        assert_eq!(error(8, 0), GameError::XOutOfBounds);
+        assert_eq!(error(0, 8), GameError::YOutOfBounds);
+        assert_eq!(error(1, 0), GameError::ClaimedTile);
+        assert_eq!(error(7, 6), GameError::OpponentTile);
+        assert_eq!(error(3, 3), GameError::NoPathToTarget);
+    }
+
+    #[test]
+    fn finds_the_path_of_a_claim() {
+        let state = play(&[(1, 0), (7, 6), (1, 1), (6, 6)]);
+
+        assert_eq!(
+            state.path_to(GridPos::new(2, 1)),
+            Some(vec![GridPos::new(2, 1)])
+        );
+        assert_eq!(state.path_to(GridPos::new(3, 1)), None);
+    }
+
+    #[test]
+    fn the_player_left_without_moves_loses() {
+        // Player two walls player one into the top-left corner.
+        let state = GameState {
+            grids: [
+                Bitboard::single(GridPos::new(0, 0)),
+                Bitboard::single(GridPos::new(1, 0))
+                    .with(GridPos::new(1, 1))
+                    .with(GridPos::new(0, 2)),
+            ],
+            ..GameState::new()
+        };
+
+        let state = state.apply_move(GridPos::new(0, 1)).unwrap();
+
+        assert_eq!(state.status, GameStatus::Completed);
+        assert_eq!(state.winner, Some(Side::Two));
+        assert!(state.legal_moves().is_empty());
+        assert_eq!(
+            state.apply_move(GridPos::new(1, 1)),
+            Err(GameError::GameNotInProgress)
+        );
+    }
+}